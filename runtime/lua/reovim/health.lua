@@ -0,0 +1,82 @@
+-- `:checkhealth reovim`, the report comes from the gui attached to this nvim.
+local health = vim.health or require('health')
+local report_start = health.start or health.report_start
+local report_ok = health.ok or health.report_ok
+local report_warn = health.warn or health.report_warn
+local report_error = health.error or health.report_error
+local report_info = health.info or health.report_info
+
+local M = {}
+
+local function channel()
+  for _, chan in ipairs(vim.api.nvim_list_chans()) do
+    if chan.client and chan.client.name == 'neovide' and chan.client.type == 'ui' then
+      return chan.id
+    end
+  end
+end
+
+function M.check()
+  report_start('reovim')
+  local chan = channel()
+  if not chan then
+    report_error('reovim is not attached to this nvim')
+    return
+  end
+  local success, report = pcall(vim.rpcrequest, chan, 'reovim.health')
+  if not success or type(report) ~= 'table' then
+    report_error('reovim did not answer: ' .. tostring(report))
+    return
+  end
+
+  report_ok('reovim ' .. report.version .. ' on channel ' .. tostring(report.channel or chan))
+  report_info('connection: ' .. report.connection)
+  if #report.extensions == 0 then
+    report_warn('no ui extensions were negotiated')
+  else
+    report_info('ui extensions: ' .. table.concat(report.extensions, ', '))
+  end
+
+  report_start('Fonts')
+  local function fonts(kind, list)
+    for _, font in ipairs(list) do
+      if font.resolved == '' then
+        report_error(kind .. ' ' .. font.family .. ' could not be loaded')
+      elseif font.resolved:lower() ~= font.family:lower() then
+        report_warn(kind .. ' ' .. font.family .. ' is not installed, ' .. font.resolved .. ' is used')
+      else
+        report_ok(kind .. ' ' .. font.family)
+      end
+    end
+  end
+  fonts('font', report.fonts)
+  fonts('wide font', report.wide_fonts)
+  if report.unknown_glyphs > 0 then
+    report_warn(report.unknown_glyphs .. ' glyphs on screen are in none of the fonts', {
+      'add a font that has them to guifont',
+    })
+  else
+    report_ok('every glyph on screen is in one of the fonts')
+  end
+
+  report_start('Clipboard')
+  report_info(report.clipboard ~= '' and report.clipboard or 'unknown')
+  if report.clipboard_provider == '' then
+    report_warn('no clipboard provider, the + and * registers are not shared with other programs', {
+      'install xclip, xsel or wl-clipboard, or set g:clipboard',
+    })
+  else
+    report_ok('clipboard provider: ' .. report.clipboard_provider)
+  end
+
+  report_start('Neovide settings')
+  if #report.unsupported == 0 then
+    report_ok('no unsupported g:neovide_ settings are set')
+  else
+    for _, variable in ipairs(report.unsupported) do
+      report_warn(variable .. ' has no equivalent in reovim and is ignored')
+    end
+  end
+end
+
+return M
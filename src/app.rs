@@ -1,14 +1,16 @@
 use std::cell::{Cell, RefCell};
 use std::rc::Rc;
-use std::sync::{atomic, Arc};
+use std::sync::atomic;
+use std::sync::Arc;
 
 use gtk::gdk;
-use gtk::gdk::prelude::FontMapExt;
+use gtk::gdk::prelude::{FontFamilyExt, FontMapExt};
 use gtk::gdk::ScrollDirection;
 use gtk::prelude::*;
 
 use adw::prelude::*;
-use once_cell::sync::{Lazy, OnceCell};
+use once_cell::sync::OnceCell;
+use pango::prelude::FontExt;
 use pango::FontDescription;
 use parking_lot::RwLock;
 use relm4::factory::FactoryVec;
@@ -17,29 +19,103 @@ use rustc_hash::FxHashMap;
 
 use crate::bridge;
 use crate::bridge::{
-    EditorMode, MouseButton, ParallelCommand, RedrawEvent, SerialCommand, UiCommand, WindowAnchor,
+    EditorMode, MouseAction, MouseButton, ParallelCommand, RedrawEvent, SerialCommand, UiCommand,
+    WindowAnchor,
 };
+use crate::color::{Color, Colors};
 use crate::components::{VimCmdEvent, VimCmdPrompts};
-use crate::cursor::{CursorMode, VimCursor};
-use crate::event_aggregator::EVENT_AGGREGATOR;
+use crate::config::{Config, ThemeConfig};
+use crate::cursor::{CursorMode, CursorSettings, VimCursor};
 use crate::grapheme::Coord;
-use crate::keys::ToInput;
-use crate::metrics::Metrics;
-use crate::vimview::{self, VimGrid, VimMessage};
+use crate::guifont::{FontSettings, GuiFont};
+use crate::instance;
+use crate::keymap::{Action, Keymap};
+use crate::keys::{self, KeyboardSettings, ToInput};
+use crate::metrics::{self, Metrics};
+use crate::pointer::{self, PointerSettings};
+use crate::scroll::{self, ScrollSettings};
+use crate::session::{self, Session};
+use crate::settings::{setting_group, ParseFromValue, SettingChanged, SettingGroup, Settings};
+use crate::state::WindowState;
+use crate::vimview::{self, GridSettings, VimGrid, VimMessage};
 use crate::Opts;
 
-#[allow(non_upper_case_globals)]
-pub static GridActived: Lazy<Arc<atomic::AtomicU64>> =
-    Lazy::new(|| Arc::new(atomic::AtomicU64::new(0)));
+const DEFAULT_FONT: &str = "monospace 11";
+
+const MIN_SCALE_FACTOR: f64 = 0.25;
+const MAX_SCALE_FACTOR: f64 = 4.;
+/// Scale step of `Ctrl+=` / `Ctrl+-`, and of a single wheel notch.
+const SCALE_STEP: f64 = 1.1;
 
 #[derive(Clone, Debug)]
 pub enum AppMessage {
     Quit,
     ShowPointer,
+    /// Font zoom, relative to the size of `guifont`.
+    ScaleFactor(f64),
+    /// The window moved to a monitor with a different scale.
+    DeviceScale(f64),
+    SettingChanged(String, nvim::Value),
+    /// A gui action bound in `g:reovim_keymap`.
+    Action(Action),
+    /// Drops the gui selection of every grid, except the one a new selection started in.
+    ClearSelection(Option<u64>),
+    /// The config file was saved.
+    ConfigChanged(Box<Config>),
+    /// Files or text dropped onto the window, with the grid cell they were dropped on.
+    Drop {
+        dropped: Dropped,
+        target: Option<Hovering>,
+        split: bool,
+    },
+    /// Files forwarded by `rv --single-instance`, relative to `cwd`.
+    OpenFiles {
+        cwd: String,
+        files: Vec<String>,
+    },
     UiCommand(UiCommand),
     RedrawEvent(RedrawEvent),
 }
 
+/// Settings owned by the gui, changes from nvim arrive as `AppMessage::SettingChanged`.
+struct GuiSettings;
+
+impl SettingGroup for GuiSettings {
+    fn register(&self, settings: &Settings) {
+        WindowSettings::default().register(settings);
+        FontSettings::default().register(settings);
+        CursorSettings::default().register(settings);
+        GridSettings::default().register(settings);
+        KeyboardSettings::default().register(settings);
+        ScrollSettings::default().register(settings);
+        // settings below are parsed in `AppMessage::SettingChanged`.
+        settings.set(&PointerSettings::default());
+        settings.set_setting_handlers(
+            "hide_mouse_when_typing",
+            |events, value| {
+                events.send(SettingChanged {
+                    name: "hide_mouse_when_typing".to_string(),
+                    value,
+                })
+            },
+            |settings| match settings.get::<PointerSettings>().hide_after {
+                Some(hide_after) => nvim::Value::from(hide_after),
+                None => nvim::Value::from(false),
+            },
+        );
+        settings.set_setting_handlers(
+            "keymap",
+            |events, value| {
+                events.send(SettingChanged {
+                    name: "keymap".to_string(),
+                    value,
+                })
+            },
+            |_| nvim::Value::Map(Vec::new()),
+        );
+    }
+}
+
 impl From<UiCommand> for AppMessage {
     fn from(cmd: UiCommand) -> Self {
         AppMessage::UiCommand(cmd)
@@ -53,14 +129,26 @@ pub struct AppModel {
     pub size: Rc<Cell<(i32, i32)>>,
     pub default_width: i32,
     pub default_height: i32,
-
-    pub guifont: Option<String>,
-    pub guifontset: Option<String>,
-    pub guifontwide: Option<String>,
+    /// restored or from `--maximized`.
+    pub maximized: bool,
+    /// connector of the monitor a restored fullscreen window goes to.
+    pub monitor: Option<String>,
+
+    pub guifont: Option<GuiFont>,
+    /// `font` of the config file, used while `guifont` is not set.
+    pub default_font: Option<GuiFont>,
+    pub guifontset: Option<GuiFont>,
+    pub guifontwide: Option<GuiFont>,
     pub metrics: Rc<Cell<Metrics>>,
     pub show_tab_line: Option<u64>,
 
     pub font_description: Rc<RefCell<pango::FontDescription>>,
+    /// runtime font zoom, `g:reovim_scale_factor`.
+    pub scale_factor: Rc<Cell<f64>>,
+    /// device pixels per logical pixel of the window surface, see `metrics::surface_scale`.
+    pub device_scale: Rc<Cell<f64>>,
+    /// font for double width cells, from `guifontwide` or `guifontset`.
+    pub font_wide_description: Rc<RefCell<Option<pango::FontDescription>>>,
     pub font_changed: Rc<atomic::AtomicBool>,
 
     pub mode: EditorMode,
@@ -75,6 +163,7 @@ pub struct AppModel {
 
     pub pctx: Rc<pango::Context>,
     pub gtksettings: OnceCell<gtk::Settings>,
+    pub window: OnceCell<gtk::ApplicationWindow>,
     pub im_context: OnceCell<gtk::IMMulticontext>,
 
     pub hldefs: Rc<RwLock<vimview::HighlightDefinitions>>,
@@ -83,29 +172,233 @@ pub struct AppModel {
     pub background_changed: Rc<atomic::AtomicBool>,
 
     pub vgrids: crate::factory::FactoryMap<vimview::VimGrid>,
+    /// nvim window handle of each window grid.
+    pub windows: FxHashMap<u64, nvim::Value>,
     pub messages: FactoryVec<vimview::VimMessage>,
 
     pub dragging: Rc<Cell<Option<Dragging>>>,
+    pub hovering: Rc<Cell<Option<Hovering>>>,
+    /// `'mousemoveevent'`, report pointer motion to nvim.
+    pub mouse_move_event: Rc<atomic::AtomicBool>,
     pub show_pointer: atomic::AtomicBool,
+    /// css name of the mouse pointer shown over the grids.
+    pub pointer: Rc<Cell<&'static str>>,
+    pub busy: bool,
+    /// `WindowSettings::fullscreen` changed, applied to the window before the next view.
+    pub fullscreen_changed: atomic::AtomicBool,
+    /// the last `g:reovim_keymap`, bound on top of `[keymap]` of the config file.
+    pub keymap: nvim::Value,
+
+    /// settings, events and health of this window, shared with its nvim side.
+    pub session: Arc<Session>,
+    /// the runtime of `session::run`, shared by all windows.
+    pub rt: tokio::runtime::Handle,
+}
+
+/// Composition text of the input method, shown at the cursor until it is committed.
+#[derive(Default)]
+struct Preedit {
+    text: String,
+    attrs: Option<pango::AttrList>,
+    /// in chars.
+    cursor: usize,
+}
+
+impl Preedit {
+    fn layout(&self, pctx: &pango::Context) -> pango::Layout {
+        let layout = pango::Layout::new(pctx);
+        layout.set_text(&self.text);
+        layout.set_attributes(self.attrs.as_ref());
+        layout
+    }
+
+    fn cursor_index(&self) -> usize {
+        self.text
+            .char_indices()
+            .nth(self.cursor)
+            .map(|(index, _)| index)
+            .unwrap_or(self.text.len())
+    }
+}
 
-    pub rt: tokio::runtime::Runtime,
+#[derive(Clone, Debug)]
+pub struct WindowSettings {
+    /// `g:reovim_fullscreen`, kept in sync with the window.
+    pub fullscreen: bool,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        WindowSettings { fullscreen: false }
+    }
+}
+
+setting_group! {
+    WindowSettings {
+        "fullscreen" => fullscreen,
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
 pub struct Dragging {
     pub btn: MouseButton,
+    /// grid the drag started in, which receives all of its events.
+    pub grid: u64,
+    /// position of that grid in the grids container.
+    pub origin: (f64, f64),
+    pub pos: (u32, u32),
+    /// a gui selection, kept from neovim.
+    pub select: bool,
+}
+
+#[derive(Clone, Debug)]
+pub enum Dropped {
+    /// Paths of local files, uris of others.
+    Files(Vec<String>),
+    Text(String),
+}
+
+/// Grid and cell under the mouse pointer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hovering {
+    pub grid: u64,
     pub pos: (u32, u32),
 }
 
+/// Default colors of `[theme]` in the config file, white on black where not given.
+fn theme_colors(theme: &ThemeConfig) -> Result<Colors, String> {
+    let parse = |color: &Option<String>, default: Color| match color {
+        Some(color) => Color::parse(color).map_err(|_| format!("invalid color '{}'", color)),
+        None => Ok(default),
+    };
+    Ok(Colors {
+        background: Some(parse(&theme.background, Color::BLACK)?),
+        foreground: Some(parse(&theme.foreground, Color::WHITE)?),
+        special: Some(parse(&theme.special, Color::WHITE)?),
+    })
+}
+
+/// The saved window state, overridden by the size and state given on the command line.
+fn initial_window_state(opts: &Opts) -> WindowState {
+    let restored = if opts.no_restore {
+        None
+    } else {
+        WindowState::load()
+    };
+    let mut state = restored.unwrap_or_else(|| {
+        let default = WindowState::default();
+        WindowState {
+            width: opts.config.window.width.unwrap_or(default.width),
+            height: opts.config.window.height.unwrap_or(default.height),
+            ..default
+        }
+    });
+    let sized = opts.width.is_some() || opts.height.is_some() || opts.geometry.is_some();
+    if sized {
+        state.maximized = false;
+        state.fullscreen = false;
+    }
+    state.width = opts.width.unwrap_or(state.width);
+    state.height = opts.height.unwrap_or(state.height);
+    state.maximized = (state.maximized || opts.maximized) && !opts.no_maximized;
+    state.fullscreen = (state.fullscreen || opts.fullscreen) && !opts.no_fullscreen;
+    state
+}
+
+/// Monitor by its connector name, like `DP-1`.
+fn find_monitor(display: &gdk::Display, connector: &str) -> Option<gdk::Monitor> {
+    let monitors = display.monitors();
+    (0..monitors.n_items())
+        .filter_map(|i| monitors.item(i)?.downcast::<gdk::Monitor>().ok())
+        .find(|monitor| monitor.connector().as_deref() == Some(connector))
+}
+
+/// Reads the clipboard without blocking and pastes it through `nvim_paste`.
+fn paste_clipboard(clipboard: &gdk::Clipboard, sender: &Sender<AppMessage>) {
+    clipboard.read_text_async(
+        gtk::gio::Cancellable::NONE,
+        glib::clone!(@strong sender => move |text| match text {
+            Ok(Some(text)) => {
+                sender
+                    .send(UiCommand::Serial(SerialCommand::Paste(text.to_string())).into())
+                    .unwrap();
+            }
+            Ok(None) => {}
+            Err(err) => {
+                log::warn!("failed to read the clipboard: {}", err);
+            }
+        }),
+    );
+}
+
+/// Sends whole cells of scrolling as wheel events to the hovered grid.
+fn send_scroll(
+    sender: &Sender<AppMessage>,
+    hovering: Hovering,
+    (cols, rows): (i64, i64),
+    modifier: gdk::ModifierType,
+) {
+    let vertical =
+        std::iter::repeat(if rows > 0 { "down" } else { "up" }).take(rows.unsigned_abs() as _);
+    let horizontal =
+        std::iter::repeat(if cols > 0 { "right" } else { "left" }).take(cols.unsigned_abs() as _);
+    for direction in vertical.chain(horizontal) {
+        log::debug!(
+            "scrolling grid {} at {:?} {}",
+            hovering.grid,
+            hovering.pos,
+            direction
+        );
+        let command = UiCommand::Serial(SerialCommand::Scroll {
+            direction: direction.into(),
+            grid_id: hovering.grid,
+            position: hovering.pos,
+            modifier,
+        });
+        sender.send(AppMessage::UiCommand(command)).unwrap();
+    }
+}
+
 impl AppModel {
-    pub fn new(opts: Opts) -> AppModel {
-        let rt = tokio::runtime::Builder::new_multi_thread()
-            .enable_time()
-            .enable_io()
-            .build()
-            .unwrap();
-        let font_desc = FontDescription::from_string("monospace 11");
-        let size = Rc::new(Cell::new((opts.width, opts.height)));
+    pub fn new(mut opts: Opts, rt: tokio::runtime::Handle) -> AppModel {
+        let session = Session::new();
+        GuiSettings.register(&session.settings);
+        let state = initial_window_state(&opts);
+        log::debug!("initial window state {:?}", state);
+        session.settings.set(&WindowSettings {
+            fullscreen: state.fullscreen,
+        });
+        let mut settings = session.settings.get::<FontSettings>();
+        settings.scale_factor = state.scale_factor;
+        session.settings.set(&settings);
+        if let Some(transparency) = opts.config.window.transparency {
+            let mut settings = session.settings.get::<GridSettings>();
+            settings.transparency = transparency;
+            session.settings.set(&settings);
+        }
+        let mut keymap = Keymap::default();
+        match keymap.extend(&opts.config.keymap_value()) {
+            Ok(()) => {
+                let mut settings = session.settings.get::<KeyboardSettings>();
+                settings.keymap = keymap;
+                session.settings.set(&settings);
+            }
+            Err(err) => {
+                log::error!("invalid [keymap] in config.toml: {}", err);
+                opts.config.keymap.clear();
+            }
+        }
+        let default_font = opts.config.font.as_deref().and_then(|font| {
+            GuiFont::parse(font)
+                .map_err(|err| log::error!("invalid font '{}' in config.toml: {}", font, err))
+                .ok()
+        });
+        let mut font_desc = default_font
+            .as_ref()
+            .map(GuiFont::font_description)
+            .unwrap_or_else(|| FontDescription::from_string(DEFAULT_FONT));
+        font_desc.set_size((font_desc.size() as f64 * state.scale_factor).round() as i32);
+        let size = Rc::new(Cell::new((state.width, state.height)));
         let pctx: Rc<pango::Context> = pangocairo::FontMap::default()
             .unwrap()
             .create_context()
@@ -125,14 +418,24 @@ impl AppModel {
             })
             .unwrap()
             .into();
+        if let Some(ref font) = default_font {
+            pangocairo::context_set_font_options(&pctx, font.font_options().as_ref());
+        }
         let hldefs = Rc::new(RwLock::new(vimview::HighlightDefinitions::new()));
+        match theme_colors(&opts.config.theme) {
+            Ok(colors) => hldefs.write().set_defaults(colors),
+            Err(err) => log::error!("invalid [theme] in config.toml: {}", err),
+        }
         let metrics = Rc::new(Metrics::new().into());
         AppModel {
             size,
             title: opts.title.clone(),
-            default_width: opts.width,
-            default_height: opts.height,
+            default_width: state.width,
+            default_height: state.height,
+            maximized: state.maximized,
+            monitor: state.monitor,
             guifont: None,
+            default_font,
             guifontset: None,
             guifontwide: None,
             show_tab_line: None,
@@ -141,7 +444,12 @@ impl AppModel {
 
             mouse_on: Rc::new(false.into()),
             cursor: MicroComponent::new(
-                VimCursor::new(pctx.clone(), Rc::clone(&metrics), hldefs.clone()),
+                VimCursor::new(
+                    pctx.clone(),
+                    Rc::clone(&metrics),
+                    hldefs.clone(),
+                    session.clone(),
+                ),
                 (),
             ),
             cursor_grid: 0,
@@ -152,10 +460,14 @@ impl AppModel {
 
             pctx,
             gtksettings: OnceCell::new(),
+            window: OnceCell::new(),
             im_context: OnceCell::new(),
 
             metrics,
             font_description: Rc::new(RefCell::new(font_desc)),
+            scale_factor: Rc::new(Cell::new(state.scale_factor)),
+            device_scale: Rc::new(Cell::new(1.)),
+            font_wide_description: Rc::new(RefCell::new(None)),
             font_changed: Rc::new(false.into()),
 
             hldefs,
@@ -164,13 +476,21 @@ impl AppModel {
             background_changed: Rc::new(false.into()),
 
             vgrids: crate::factory::FactoryMap::new(),
+            windows: FxHashMap::default(),
             messages: FactoryVec::new(),
 
             dragging: Rc::new(Cell::new(None)),
+            hovering: Rc::new(Cell::new(None)),
+            mouse_move_event: Rc::new(false.into()),
             show_pointer: true.into(),
+            pointer: Rc::new(Cell::new(pointer::TEXT)),
+            busy: false,
+            fullscreen_changed: false.into(),
+            keymap: nvim::Value::Map(Vec::new()),
 
             opts,
 
+            session,
             rt,
         }
     }
@@ -195,6 +515,11 @@ impl AppModel {
             desc.style(),
             desc.size() / pango::SCALE,
         );
+        // measure with hinting at the resolution we render at.
+        let scale = self.device_scale.get();
+        let matrix = self.pctx.matrix();
+        self.pctx
+            .set_matrix(Some(&pango::Matrix::new(scale, 0., 0., scale, 0., 0.)));
         let layout = pango::Layout::new(&self.pctx);
         layout.set_font_description(Some(&desc));
         let mut tabs = pango::TabArray::new(1, false);
@@ -217,32 +542,443 @@ impl AppModel {
         let fm_width = font_metrics.approximate_digit_width();
         let fm_height = font_metrics.height();
         let fm_ascent = font_metrics.ascent();
+        // pango measures the underline position upwards from the baseline.
+        let underline_position = -font_metrics.underline_position() as f64 / PANGO_SCALE;
+        let underline_thickness = font_metrics.underline_thickness() as f64 / PANGO_SCALE;
+        self.pctx.set_matrix(matrix.as_ref());
         log::info!("font-metrics width: {}", fm_width as f64 / PANGO_SCALE);
         log::info!("font-metrics height: {}", fm_height as f64 / PANGO_SCALE);
         log::info!("font-metrics ascent: {}", fm_ascent as f64 / PANGO_SCALE);
         let mut metrics = self.metrics.get();
         let charwidth = max_width as f64 / PANGO_SCALE;
-        let width = charwidth;
+        let width = charwidth + self.guifont.as_ref().map(|font| font.width).unwrap_or(0.);
         let charheight = if fm_height > 0 {
             fm_height.min(max_height) as f64 / PANGO_SCALE
         } else {
             max_height as f64 / PANGO_SCALE
         };
+        // keep cells on whole device pixels.
+        let align = |v: f64| (v * scale).ceil() / scale;
+        let (width, ascent) = (align(width).max(1.), align(ascent));
+        let (charwidth, charheight) = (align(charwidth), align(charheight));
+        let underline_position = (underline_position * scale).round() / scale;
+        let underline_thickness = align(underline_thickness.max(1. / scale));
         if metrics.charheight() == charheight
             && metrics.charwidth() == charwidth
             && metrics.width() == width
+            && metrics.ascent() == ascent
+            && metrics.underline_position() == underline_position
+            && metrics.underline_thickness() == underline_thickness
         {
             return;
         }
-        metrics.set_width(width.ceil());
-        metrics.set_ascent(ascent.ceil());
-        metrics.set_charwidth(charwidth.ceil());
-        metrics.set_charheight(charheight.ceil());
+        metrics.set_underline_position(underline_position);
+        metrics.set_underline_thickness(underline_thickness);
+        metrics.set_width(width);
+        metrics.set_ascent(ascent);
+        metrics.set_charwidth(charwidth);
+        metrics.set_charheight(charheight);
         log::info!("char-width {:?}", metrics.charwidth());
         log::info!("char-height {:?}", metrics.charheight());
         log::info!("char-ascent {:?}", metrics.ascent());
         self.metrics.replace(metrics);
     }
+
+    fn set_guifont(&mut self, font: GuiFont) {
+        self.guifont.replace(font);
+        pangocairo::context_set_font_options(&self.pctx, self.font_options().as_ref());
+        self.update_font();
+    }
+
+    /// Options of `guifont`, or the default font, with `g:reovim_font_antialias` and
+    /// `g:reovim_font_hinting` for what it leaves out.
+    fn font_options(&self) -> Option<cairo::FontOptions> {
+        let settings = self.session.settings.get::<FontSettings>();
+        let mut font = self
+            .guifont
+            .as_ref()
+            .or(self.default_font.as_ref())
+            .cloned()
+            .unwrap_or_default();
+        font.antialias = font.antialias.or(settings.antialias);
+        font.hinting = font.hinting.or(settings.hinting);
+        font.font_options()
+    }
+
+    /// Returns false if the scale factor did not change.
+    fn set_scale_factor(&mut self, scale_factor: f64) -> bool {
+        let scale_factor = scale_factor.clamp(MIN_SCALE_FACTOR, MAX_SCALE_FACTOR);
+        // stored clamped, also when it does not change the font.
+        let mut settings = self.session.settings.get::<FontSettings>();
+        settings.scale_factor = scale_factor;
+        self.session.settings.set(&settings);
+        if (scale_factor - self.scale_factor.get()).abs() < 1e-3 {
+            return false;
+        }
+        log::info!("font scale factor {}", scale_factor);
+        self.scale_factor.set(scale_factor);
+        self.update_font();
+        true
+    }
+
+    fn scaled_size(&self, size: i32) -> i32 {
+        (size as f64 * self.scale_factor.get()).round().max(1.) as i32
+    }
+
+    /// Rebuilds the font description from `guifont` and the scale factor, window size is kept,
+    /// nvim gets resized in `pre_view`.
+    fn update_font(&mut self) {
+        let mut desc = self
+            .guifont
+            .as_ref()
+            .or(self.default_font.as_ref())
+            .map(GuiFont::font_description)
+            .unwrap_or_else(|| FontDescription::from_string(DEFAULT_FONT));
+        desc.set_size(self.scaled_size(desc.size()));
+
+        self.pctx.set_font_description(&desc);
+        self.gtksettings.get().map(|settings| {
+            settings.set_gtk_font_name(Some(&desc.to_str()));
+        });
+
+        self.session.health.write().fonts = self.resolve_fonts(&desc);
+        self.font_description.replace(desc);
+
+        self.calculate();
+
+        // resets cache of all grids.
+        self.update_font_wide();
+
+        self.font_changed.store(true, atomic::Ordering::Relaxed);
+        self.cursor_coord_changed
+            .store(true, atomic::Ordering::Relaxed);
+    }
+
+    /// Each family of the fallback list and the family pango loads for it.
+    fn resolve_fonts(&self, desc: &FontDescription) -> Vec<(String, String)> {
+        let families = desc.family().map(|f| f.to_string()).unwrap_or_default();
+        families
+            .split(',')
+            .map(str::trim)
+            .filter(|family| !family.is_empty())
+            .map(|family| {
+                let mut desc = desc.clone();
+                desc.set_family(family);
+                let resolved = self
+                    .pctx
+                    .load_font(&desc)
+                    .and_then(|font| font.describe().family())
+                    .map(|family| family.to_string())
+                    .unwrap_or_default();
+                (family.to_string(), resolved)
+            })
+            .collect()
+    }
+
+    /// `guifontwide` takes precedence over `guifontset`, size follows `guifont` if not specified.
+    fn update_font_wide(&mut self) {
+        let desc = self
+            .guifontwide
+            .as_ref()
+            .or(self.guifontset.as_ref())
+            .map(|font| {
+                let mut desc = font.font_description();
+                if font.size.is_none() {
+                    desc.set_size(self.font_description.borrow().size());
+                } else {
+                    desc.set_size(self.scaled_size(desc.size()));
+                }
+                desc
+            });
+        log::info!(
+            "wide font: {:?}",
+            desc.as_ref().map(|desc| desc.to_str().to_string())
+        );
+        self.session.health.write().wide_fonts = desc
+            .as_ref()
+            .map(|desc| self.resolve_fonts(desc))
+            .unwrap_or_default();
+        self.font_wide_description.replace(desc);
+        self.vgrids
+            .iter_mut()
+            .for_each(|(_, vgrid)| vgrid.reset_cache());
+    }
+
+    /// Families pango does not know about will silently fall back to others.
+    fn report_missing_families(&self, font: &GuiFont, sender: &Sender<AppMessage>) {
+        const ALIASES: &[&str] = &["monospace", "sans", "sans-serif", "serif", "system-ui"];
+        let families: Vec<String> = self
+            .pctx
+            .list_families()
+            .iter()
+            .filter_map(|family| family.name())
+            .map(|name| name.to_lowercase())
+            .collect();
+        font.families
+            .iter()
+            .filter(|family| {
+                let family = family.to_lowercase();
+                !ALIASES.contains(&family.as_str()) && !families.contains(&family)
+            })
+            .for_each(|family| {
+                log::warn!("font family '{}' not found", family);
+                self.report_error(
+                    format!("E596: Invalid font(s): '{}' not found", family),
+                    sender,
+                );
+            });
+    }
+
+    /// Marks every grid as changed.
+    fn redraw_grids(&mut self) {
+        let grids: Vec<u64> = self.vgrids.iter().map(|(k, _)| *k).collect();
+        for grid in grids {
+            self.vgrids.queue_update(grid);
+        }
+        self.vgrids.flush();
+    }
+
+    /// Pointer for the busy state, the mode and the cell under the pointer.
+    fn update_pointer(&self) {
+        let pointer = if self.busy {
+            pointer::BUSY
+        } else {
+            self.hovering
+                .get()
+                .and_then(|hovering| {
+                    let (col, row) = hovering.pos;
+                    let vgrid = self.vgrids.get(hovering.grid)?;
+                    let url = vgrid.textbuf().borrow().url(row as _, col as _);
+                    url.map(|_| pointer::URL)
+                })
+                .or_else(|| {
+                    self.hovering
+                        .get()
+                        .filter(|hovering| hovering.grid == 1)
+                        .and_then(|hovering| pointer::probe(&self.window_rects(), hovering.pos))
+                })
+                .or_else(|| {
+                    self.cursor_modes
+                        .get(self.cursor_mode)
+                        .and_then(|mode| mode.mouse_shape)
+                })
+                .unwrap_or(pointer::TEXT)
+        };
+        self.pointer.set(pointer);
+    }
+
+    /// Windows laid out on the default grid, floats left out.
+    fn window_rects(&self) -> Vec<pointer::WindowRect> {
+        self.vgrids
+            .iter()
+            .filter(|(grid, vgrid)| {
+                self.windows.contains_key(grid) && vgrid.is_visible() && !vgrid.is_float()
+            })
+            .map(|(_, vgrid)| pointer::WindowRect {
+                col: vgrid.coord().col as u32,
+                row: vgrid.coord().row as u32,
+                width: vgrid.width() as u32,
+                height: vgrid.height() as u32,
+            })
+            .collect()
+    }
+
+    /// Saves the window for the next launch, see `state::WindowState`.
+    fn save_window_state(&self) {
+        let window = match self.window.get() {
+            Some(window) if !self.opts.no_restore => window,
+            _ => return,
+        };
+        // the size the window has when it is not maximized.
+        let (width, height) = window.default_size();
+        let monitor = window
+            .display()
+            .monitor_at_surface(&window.surface())
+            .and_then(|monitor| monitor.connector())
+            .map(|connector| connector.to_string());
+        let state = WindowState {
+            width,
+            height,
+            maximized: window.is_maximized(),
+            fullscreen: window.is_fullscreen(),
+            scale_factor: self.scale_factor.get(),
+            monitor,
+        };
+        log::debug!("saving window state {:?}", state);
+        if let Err(err) = state.save() {
+            log::warn!("could not save the window state: {}", err);
+        }
+    }
+
+    /// Bindings of `[keymap]` in the config file, with `g:reovim_keymap` on top.
+    fn build_keymap(&self) -> Result<Keymap, String> {
+        let mut keymap = Keymap::default();
+        keymap
+            .extend(&self.opts.config.keymap_value())
+            .map_err(|err| format!("Invalid [keymap] in config.toml: {}", err))?;
+        keymap
+            .extend(&self.keymap)
+            .map_err(|err| format!("Invalid g:reovim_keymap: {}", err))?;
+        Ok(keymap)
+    }
+
+    /// Applies what of the config file can change at runtime: the default font, key bindings
+    /// and transparency. The window and connection options only apply on start.
+    fn reload_config(&mut self, mut config: Config, sender: &Sender<AppMessage>) {
+        if let Err(err) = Keymap::default().extend(&config.keymap_value()) {
+            self.report_error(format!("Invalid [keymap] in config.toml: {}", err), sender);
+            config.keymap.clear();
+        }
+        let font_changed = config.font != self.opts.config.font;
+        let transparency = config.window.transparency;
+        let transparency_changed = transparency != self.opts.config.window.transparency;
+        self.opts.config = config;
+
+        if font_changed {
+            let font = self.opts.config.font.as_deref().map(GuiFont::parse);
+            match font.transpose() {
+                Ok(font) => {
+                    self.default_font = font;
+                    if self.guifont.is_none() {
+                        pangocairo::context_set_font_options(
+                            &self.pctx,
+                            self.font_options().as_ref(),
+                        );
+                        self.update_font();
+                    }
+                }
+                Err(err) => {
+                    self.report_error(format!("Invalid font in config.toml: {}", err), sender);
+                }
+            }
+        }
+
+        match self.build_keymap() {
+            Ok(keymap) => {
+                let mut settings = self.session.settings.get::<KeyboardSettings>();
+                settings.keymap = keymap;
+                self.session.settings.set(&settings);
+            }
+            Err(err) => self.report_error(err, sender),
+        }
+
+        if transparency_changed {
+            if let Some(transparency) = transparency {
+                sender
+                    .send(
+                        UiCommand::Parallel(ParallelCommand::SetSetting {
+                            name: "transparency".to_string(),
+                            value: nvim::Value::from(transparency),
+                        })
+                        .into(),
+                    )
+                    .unwrap();
+            }
+        }
+    }
+
+    /// Applies a setting nvim changed, those of a `setting_group!` are already stored.
+    fn apply_setting(&mut self, name: &str, value: nvim::Value, sender: &Sender<AppMessage>) {
+        match name {
+            "scale_factor" => {
+                let requested = self.session.settings.get::<FontSettings>().scale_factor;
+                self.set_scale_factor(requested);
+                // out of range, `g:reovim_scale_factor` gets the clamped value.
+                if (requested - self.scale_factor.get()).abs() >= 1e-3 {
+                    self.sync_setting("scale_factor", sender);
+                }
+            }
+            "font_antialias" | "font_hinting" => {
+                pangocairo::context_set_font_options(&self.pctx, self.font_options().as_ref());
+                self.update_font();
+            }
+            "cursor_blink" => {
+                self.cursor.update_view().unwrap();
+            }
+            "undercurl_amplitude" => {
+                self.redraw_grids();
+            }
+            "builtin_box_drawing" | "transparent_highlights" | "transparency" => {
+                // cached layouts have the glyphs blanked out, or their backgrounds translucent.
+                self.vgrids
+                    .iter_mut()
+                    .for_each(|(_, vgrid)| vgrid.reset_cache());
+                self.redraw_grids();
+                if name == "transparency" {
+                    self.background_changed
+                        .store(true, atomic::Ordering::Relaxed);
+                }
+            }
+            "fullscreen" => {
+                self.fullscreen_changed
+                    .store(true, atomic::Ordering::Relaxed);
+            }
+            // read on every key press or scroll.
+            "latin_shortcuts" | "alt_as_meta" | "super_as_meta" | "scroll_multiplier"
+            | "kinetic_scrolling" => {}
+            "hide_mouse_when_typing" => {
+                let hide_after = match bool::parse_from_value(&value).ok() {
+                    // numbers are the timeout, in milliseconds.
+                    Some(true) if value.is_bool() => Some(Some(1000)),
+                    Some(true) => value.as_u64().map(|ms| Some(ms as u32)),
+                    Some(false) => Some(None),
+                    None => None,
+                };
+                match hide_after {
+                    Some(hide_after) => {
+                        self.session.settings.set(&PointerSettings { hide_after });
+                    }
+                    None => {
+                        self.report_error(
+                            format!(
+                                "Invalid value for g:reovim_hide_mouse_when_typing: {}",
+                                value
+                            ),
+                            sender,
+                        );
+                    }
+                }
+            }
+            "keymap" => {
+                let previous = std::mem::replace(&mut self.keymap, value);
+                match self.build_keymap() {
+                    Ok(keymap) => {
+                        let mut settings = self.session.settings.get::<KeyboardSettings>();
+                        settings.keymap = keymap;
+                        self.session.settings.set(&settings);
+                    }
+                    Err(err) => {
+                        self.keymap = previous;
+                        self.report_error(err, sender);
+                    }
+                }
+            }
+            _ => {
+                log::warn!("unhandled setting {} = {}", name, value);
+            }
+        }
+    }
+
+    /// Pushes a setting changed in the gui, like the zoom, to its `g:reovim_{name}`.
+    fn sync_setting(&self, name: &str, sender: &Sender<AppMessage>) {
+        if let Some(value) = self.session.settings.value(name) {
+            sender
+                .send(
+                    UiCommand::Parallel(ParallelCommand::SetSetting {
+                        name: name.to_string(),
+                        value,
+                    })
+                    .into(),
+                )
+                .unwrap();
+        }
+    }
+
+    fn report_error(&self, message: String, sender: &Sender<AppMessage>) {
+        sender
+            .send(UiCommand::Parallel(ParallelCommand::ErrWriteln(message)).into())
+            .unwrap();
+    }
 }
 
 impl Model for AppModel {
@@ -261,14 +997,177 @@ impl AppUpdate for AppModel {
         match message {
             AppMessage::UiCommand(ui_command) => {
                 log::trace!("ui-commad {:?}", ui_command);
-                EVENT_AGGREGATOR.send(ui_command);
+                self.session.events.send(ui_command);
             }
             AppMessage::Quit => {
+                self.save_window_state();
                 return false;
             }
             AppMessage::ShowPointer => {
+                self.update_pointer();
                 self.show_pointer.store(true, atomic::Ordering::Relaxed);
             }
+            AppMessage::ScaleFactor(scale_factor) => {
+                if self.set_scale_factor(scale_factor) {
+                    self.sync_setting("scale_factor", &sender);
+                }
+            }
+            AppMessage::Action(action) => match action {
+                Action::Copy => {
+                    let selected = self
+                        .vgrids
+                        .iter()
+                        .find_map(|(_, vgrid)| vgrid.selected_text());
+                    match selected {
+                        Some(text) => gdk::Display::default().unwrap().clipboard().set_text(&text),
+                        None => sender
+                            .send(UiCommand::Serial(SerialCommand::Copy).into())
+                            .unwrap(),
+                    }
+                }
+                Action::Paste => {
+                    paste_clipboard(&gdk::Display::default().unwrap().clipboard(), &sender);
+                }
+                Action::PastePrimary => {
+                    paste_clipboard(
+                        &gdk::Display::default().unwrap().primary_clipboard(),
+                        &sender,
+                    );
+                }
+                Action::ZoomIn => {
+                    let scale_factor = self.scale_factor.get() * SCALE_STEP;
+                    sender.send(AppMessage::ScaleFactor(scale_factor)).unwrap();
+                }
+                Action::ZoomOut => {
+                    let scale_factor = self.scale_factor.get() / SCALE_STEP;
+                    sender.send(AppMessage::ScaleFactor(scale_factor)).unwrap();
+                }
+                Action::ZoomReset => {
+                    sender.send(AppMessage::ScaleFactor(1.)).unwrap();
+                }
+                Action::ToggleFullscreen => {
+                    let mut settings = self.session.settings.get::<WindowSettings>();
+                    settings.fullscreen = !settings.fullscreen;
+                    self.session.settings.set(&settings);
+                    self.fullscreen_changed
+                        .store(true, atomic::Ordering::Relaxed);
+                }
+                Action::NewWindow => {
+                    let app = self.window.get().and_then(|window| window.application());
+                    match app {
+                        Some(app) => {
+                            // the options this window started with, without its files.
+                            let opts = Opts {
+                                files: Vec::new(),
+                                ..self.opts.clone()
+                            };
+                            session::open_window(&app, opts, self.rt.clone());
+                        }
+                        None => self.report_error(
+                            "Could not open a new window: no application is running.".to_string(),
+                            &sender,
+                        ),
+                    }
+                }
+                Action::Keys(keys) => {
+                    sender
+                        .send(UiCommand::Serial(SerialCommand::Keyboard(keys)).into())
+                        .unwrap();
+                }
+            },
+            AppMessage::ClearSelection(except) => {
+                let cleared: Vec<u64> = self
+                    .vgrids
+                    .iter()
+                    .filter(|(grid, _)| Some(**grid) != except)
+                    .filter(|(_, vgrid)| vgrid.clear_selection())
+                    .map(|(grid, _)| *grid)
+                    .collect();
+                for grid in cleared {
+                    self.vgrids.queue_update(grid);
+                }
+                self.vgrids.flush();
+            }
+            AppMessage::ConfigChanged(config) => {
+                log::info!("config file changed: {:?}", config);
+                self.reload_config(*config, &sender);
+            }
+            AppMessage::Drop {
+                dropped,
+                target,
+                split,
+            } => match dropped {
+                Dropped::Files(files) => {
+                    let window = target.and_then(|target| self.windows.get(&target.grid).cloned());
+                    sender
+                        .send(
+                            UiCommand::Parallel(ParallelCommand::FileDrop {
+                                files,
+                                window,
+                                split,
+                            })
+                            .into(),
+                        )
+                        .unwrap();
+                }
+                Dropped::Text(text) => {
+                    // a click moves the cursor to the drop cell first.
+                    if let Some(Hovering { grid, pos }) = target {
+                        for action in [MouseAction::Press, MouseAction::Release] {
+                            let click = SerialCommand::MouseButton {
+                                action,
+                                button: MouseButton::Left,
+                                modifier: gdk::ModifierType::empty(),
+                                clicks: 1,
+                                grid_id: grid,
+                                position: pos,
+                            };
+                            sender.send(UiCommand::Serial(click).into()).unwrap();
+                        }
+                    }
+                    sender
+                        .send(UiCommand::Serial(SerialCommand::Paste(text)).into())
+                        .unwrap();
+                }
+            },
+            AppMessage::OpenFiles { cwd, files } => {
+                if let Some(window) = self.window.get() {
+                    window.present();
+                }
+                if !files.is_empty() {
+                    let files = instance::absolute_paths(&cwd, &files);
+                    sender
+                        .send(UiCommand::Parallel(ParallelCommand::OpenFiles(files)).into())
+                        .unwrap();
+                }
+            }
+            AppMessage::DeviceScale(device_scale) => {
+                if device_scale != self.device_scale.get() {
+                    log::info!("device scale changed to {}", device_scale);
+                    self.device_scale.set(device_scale);
+                    self.calculate();
+                    // layouts cached for the old scale are hinted wrong.
+                    self.update_font_wide();
+                    self.vgrids.iter_mut().for_each(|(_, vgrid)| {
+                        let coord = vgrid.coord().clone();
+                        vgrid.set_coord(coord.col, coord.row);
+                    });
+                    self.redraw_grids();
+                    self.font_changed.store(true, atomic::Ordering::Relaxed);
+                    self.cursor_coord_changed
+                        .store(true, atomic::Ordering::Relaxed);
+                }
+            }
+            AppMessage::SettingChanged(name, value) => {
+                if let Some(Err(err)) = self.session.settings.parse(&name, &value) {
+                    self.report_error(
+                        format!("Invalid value for g:reovim_{}: {}", name, err),
+                        &sender,
+                    );
+                    return true;
+                }
+                self.apply_setting(&name, value, &sender);
+            }
             AppMessage::RedrawEvent(event) => {
                 match event {
                     RedrawEvent::SetTitle { title } => {
@@ -278,67 +1177,122 @@ impl AppUpdate for AppModel {
                             .collect::<Vec<_>>()
                             .join("  ")
                     }
-                    RedrawEvent::OptionSet { gui_option } => match gui_option {
-                        bridge::GuiOption::AmbiWidth(ambi_width) => {
-                            log::debug!("unhandled ambi_width {}", ambi_width);
-                        }
-                        bridge::GuiOption::ArabicShape(arabic_shape) => {
-                            log::debug!("unhandled arabic-shape: {}", arabic_shape);
-                        }
-                        bridge::GuiOption::Emoji(emoji) => {
-                            log::debug!("emoji: {}", emoji);
-                        }
-                        bridge::GuiOption::GuiFont(guifont) => {
-                            if !guifont.trim().is_empty() {
-                                log::info!("gui font: {}", &guifont);
-                                let desc = pango::FontDescription::from_string(
-                                    &guifont.replace(":h", " "),
-                                );
-
-                                self.pctx.set_font_description(&desc);
-                                self.gtksettings.get().map(|settings| {
-                                    settings.set_gtk_font_name(Some(&desc.to_str()));
-                                });
-
-                                self.guifont.replace(guifont);
-                                self.font_description.replace(desc);
-
-                                self.calculate();
-
-                                self.vgrids
-                                    .iter_mut()
-                                    .for_each(|(_, vgrid)| vgrid.reset_cache());
-
-                                self.font_changed.store(true, atomic::Ordering::Relaxed);
-                                self.cursor_coord_changed
-                                    .store(true, atomic::Ordering::Relaxed);
+                    RedrawEvent::OptionSet { gui_option } => {
+                        match gui_option {
+                            bridge::GuiOption::AmbiWidth(ambi_width) => {
+                                log::debug!("unhandled ambi_width {}", ambi_width);
+                            }
+                            bridge::GuiOption::ArabicShape(arabic_shape) => {
+                                log::debug!("unhandled arabic-shape: {}", arabic_shape);
+                            }
+                            bridge::GuiOption::Emoji(emoji) => {
+                                log::debug!("emoji: {}", emoji);
+                            }
+                            bridge::GuiOption::GuiFont(guifont) => {
+                                if guifont.trim() == "*" {
+                                    let fonts = self
+                                        .pctx
+                                        .list_families()
+                                        .iter()
+                                        .filter(|family| family.is_monospace())
+                                        .filter_map(|family| family.name())
+                                        .map(|name| name.to_string())
+                                        .collect();
+                                    sender
+                                        .send(
+                                            UiCommand::Parallel(
+                                                ParallelCommand::DisplayAvailableFonts(fonts),
+                                            )
+                                            .into(),
+                                        )
+                                        .unwrap();
+                                } else if !guifont.trim().is_empty() {
+                                    log::info!("gui font: {}", &guifont);
+                                    match GuiFont::parse(&guifont) {
+                                        Ok(font) => {
+                                            self.report_missing_families(&font, &sender);
+                                            self.set_guifont(font);
+                                        }
+                                        Err(err) => {
+                                            log::error!("invalid guifont '{}': {}", guifont, err);
+                                            self.report_error(
+                                                format!(
+                                                    "E596: Invalid font(s): guifont={}: {}",
+                                                    guifont, err
+                                                ),
+                                                &sender,
+                                            );
+                                        }
+                                    }
+                                }
+                            }
+                            bridge::GuiOption::GuiFontSet(guifontset) => {
+                                if guifontset.trim().is_empty() {
+                                    self.guifontset.take();
+                                    self.update_font_wide();
+                                } else {
+                                    match GuiFont::parse(&guifontset) {
+                                        Ok(font) => {
+                                            self.report_missing_families(&font, &sender);
+                                            self.guifontset.replace(font);
+                                            self.update_font_wide();
+                                        }
+                                        Err(err) => {
+                                            self.report_error(
+                                                format!(
+                                                    "E598: Invalid fontset: guifontset={}: {}",
+                                                    guifontset, err
+                                                ),
+                                                &sender,
+                                            );
+                                        }
+                                    }
+                                }
+                            }
+                            bridge::GuiOption::GuiFontWide(guifontwide) => {
+                                if guifontwide.trim().is_empty() {
+                                    self.guifontwide.take();
+                                    self.update_font_wide();
+                                } else {
+                                    match GuiFont::parse(&guifontwide) {
+                                        Ok(font) => {
+                                            self.report_missing_families(&font, &sender);
+                                            self.guifontwide.replace(font);
+                                            self.update_font_wide();
+                                        }
+                                        Err(err) => {
+                                            self.report_error(
+                                            format!("E533: Can't select wide font: guifontwide={}: {}", guifontwide, err),
+                                            &sender,
+                                        );
+                                        }
+                                    }
+                                }
+                            }
+                            bridge::GuiOption::LineSpace(linespace) => {
+                                log::info!("line space: {}", linespace);
+                                let mut metrics = self.metrics.get();
+                                metrics.set_linespace(linespace as _);
+                                self.metrics.replace(metrics);
+                            }
+                            bridge::GuiOption::MouseMoveEvent(mouse_move_event) => {
+                                self.mouse_move_event
+                                    .store(mouse_move_event, atomic::Ordering::Relaxed);
+                            }
+                            bridge::GuiOption::ShowTabLine(show_tab_line) => {
+                                self.show_tab_line.replace(show_tab_line);
+                            }
+                            bridge::GuiOption::TermGuiColors(term_gui_colors) => {
+                                log::debug!("unhandled term gui colors: {}", term_gui_colors);
+                            }
+                            bridge::GuiOption::Pumblend(pumblend) => {
+                                log::debug!("unhandled pumblend: {}", pumblend)
+                            }
+                            bridge::GuiOption::Unknown(name, value) => {
+                                log::debug!("GuiOption({}: {:?}) not supported yet.", name, value)
                             }
                         }
-                        bridge::GuiOption::GuiFontSet(guifontset) => {
-                            self.guifontset.replace(guifontset);
-                        }
-                        bridge::GuiOption::GuiFontWide(guifontwide) => {
-                            self.guifontwide.replace(guifontwide);
-                        }
-                        bridge::GuiOption::LineSpace(linespace) => {
-                            log::info!("line space: {}", linespace);
-                            let mut metrics = self.metrics.get();
-                            metrics.set_linespace(linespace as _);
-                            self.metrics.replace(metrics);
-                        }
-                        bridge::GuiOption::ShowTabLine(show_tab_line) => {
-                            self.show_tab_line.replace(show_tab_line);
-                        }
-                        bridge::GuiOption::TermGuiColors(term_gui_colors) => {
-                            log::debug!("unhandled term gui colors: {}", term_gui_colors);
-                        }
-                        bridge::GuiOption::Pumblend(pumblend) => {
-                            log::debug!("unhandled pumblend: {}", pumblend)
-                        }
-                        bridge::GuiOption::Unknown(name, value) => {
-                            log::debug!("GuiOption({}: {:?}) not supported yet.", name, value)
-                        }
-                    },
+                    }
                     RedrawEvent::DefaultColorsSet { colors } => {
                         self.background_changed
                             .store(true, atomic::Ordering::Relaxed);
@@ -360,6 +1314,7 @@ impl AppUpdate for AppModel {
                         row,
                         column_start,
                         cells,
+                        wrap,
                     } => {
                         log::debug!(
                             "grid {} line - {} cells at {}x{}",
@@ -378,6 +1333,7 @@ impl AppUpdate for AppModel {
                             .textbuf()
                             .borrow()
                             .set_cells(row as _, column_start as _, &cells);
+                        vgrid.textbuf().borrow().set_wrap(row as _, wrap);
                         let row = row as usize;
                         let coord = &self.cursor_coord;
                         let cursor_grid = self.cursor_grid;
@@ -464,17 +1420,22 @@ impl AppUpdate for AppModel {
                                 (width, height).into(),
                                 self.hldefs.clone(),
                                 self.dragging.clone(),
+                                self.hovering.clone(),
+                                self.mouse_move_event.clone(),
+                                self.mouse_on.clone(),
                                 self.metrics.clone(),
                                 self.font_description.clone(),
+                                self.session.clone(),
                             );
                             vgrid.set_pango_context(self.pctx.clone());
+                            vgrid.set_font_wide(self.font_wide_description.clone());
                             self.vgrids.insert(grid, vgrid);
                         };
                     }
 
                     RedrawEvent::WindowPosition {
                         grid,
-                        window: _,
+                        window,
                         start_row: row,
                         start_column: column,
                         width,
@@ -483,6 +1444,7 @@ impl AppUpdate for AppModel {
                         // let metrics = self.metrics.get();
                         // let x = start_column as f64 * metrics.width();
                         // let y = start_row as f64 * metrics.height(); //;
+                        self.windows.insert(grid, window.get_value().clone());
 
                         if self.vgrids.get(grid).is_none() {
                             // dose not exists, create
@@ -493,10 +1455,15 @@ impl AppUpdate for AppModel {
                                 (width, height).into(),
                                 self.hldefs.clone(),
                                 self.dragging.clone(),
+                                self.hovering.clone(),
+                                self.mouse_move_event.clone(),
+                                self.mouse_on.clone(),
                                 self.metrics.clone(),
                                 self.font_description.clone(),
+                                self.session.clone(),
                             );
                             vgrid.set_pango_context(self.pctx.clone());
+                            vgrid.set_font_wide(self.font_wide_description.clone());
                             self.vgrids.insert(grid, vgrid);
                             log::info!(
                                 "Add grid {} at {}x{} with {}x{}.",
@@ -528,7 +1495,7 @@ impl AppUpdate for AppModel {
                     }
                     RedrawEvent::WindowViewport {
                         grid,
-                        window: _,
+                        window,
                         top_line,
                         bottom_line,
                         current_line,
@@ -539,6 +1506,7 @@ impl AppUpdate for AppModel {
                             "WindowViewport grid {} viewport: top({}) bottom({}) highlight-line({}) highlight-column({}) with {} lines",
                              grid, top_line, bottom_line, current_line, current_column, line_count,
                         );
+                        self.windows.insert(grid, window.get_value().clone());
 
                         if self.vgrids.get(grid).is_none() {
                             log::warn!("WindowViewport before create grid {}.", grid);
@@ -553,11 +1521,15 @@ impl AppUpdate for AppModel {
                     }
                     RedrawEvent::WindowClose { grid } => {
                         log::info!("grid {} closed", grid);
+                        self.session.health.write().forget_grid(grid);
                         self.vgrids.remove(grid);
+                        self.windows.remove(&grid);
                     }
                     RedrawEvent::Destroy { grid } => {
                         log::info!("grid {} destroyed", grid);
+                        self.session.health.write().forget_grid(grid);
                         self.vgrids.remove(grid);
+                        self.windows.remove(&grid);
                     }
                     RedrawEvent::Flush => {
                         self.vgrids.flush();
@@ -613,6 +1585,7 @@ impl AppUpdate for AppModel {
                             })
                             .unwrap();
                         self.cursor.update_view().unwrap();
+                        self.update_pointer();
                     }
                     RedrawEvent::ModeChange { mode, mode_index } => {
                         self.mode = mode;
@@ -626,16 +1599,17 @@ impl AppUpdate for AppModel {
                             })
                             .unwrap();
                         self.cursor.update_view().unwrap();
+                        self.update_pointer();
                         if matches!(self.mode, EditorMode::Normal | EditorMode::Unknown(_)) {
                             sender.send(AppMessage::ShowPointer).unwrap();
                         }
                     }
                     RedrawEvent::BusyStart => {
-                        log::debug!("Ignored BusyStart.");
+                        self.busy = true;
                         sender.send(AppMessage::ShowPointer).unwrap();
                     }
                     RedrawEvent::BusyStop => {
-                        log::debug!("Ignored BusyStop.");
+                        self.busy = false;
                         sender.send(AppMessage::ShowPointer).unwrap();
                     }
                     RedrawEvent::MouseOn => {
@@ -661,6 +1635,7 @@ impl AppUpdate for AppModel {
                             self.hldefs.clone(),
                             self.metrics.clone(),
                             self.pctx.clone(),
+                            self.session.clone(),
                         ))
                     }
                     RedrawEvent::MessageShowMode { content } => {
@@ -705,11 +1680,16 @@ impl AppUpdate for AppModel {
                                 (width, 1).into(),
                                 self.hldefs.clone(),
                                 self.dragging.clone(),
+                                self.hovering.clone(),
+                                self.mouse_move_event.clone(),
+                                self.mouse_on.clone(),
                                 self.metrics.clone(),
                                 self.font_description.clone(),
+                                self.session.clone(),
                             );
                             vgrid.show();
                             vgrid.set_pango_context(self.pctx.clone());
+                            vgrid.set_font_wide(self.font_wide_description.clone());
                             self.vgrids.insert(grid, vgrid);
                         }
                     }
@@ -823,7 +1803,7 @@ impl Widgets<AppModel, ()> for AppWidgets {
         main_window = gtk::ApplicationWindow {
             set_default_width: model.default_width,
             set_default_height: model.default_height,
-            set_cursor_from_name: Some("text"),
+            set_cursor_from_name: Some(pointer::TEXT),
             set_title: watch!(Some(&model.title)),
             set_child: vbox = Some(&gtk::Box) {
                 set_orientation: gtk::Orientation::Vertical,
@@ -866,13 +1846,14 @@ impl Widgets<AppModel, ()> for AppWidgets {
                                 )
                                 .unwrap();
                         },
-                        set_draw_func[hldefs = model.hldefs.clone()] => move |_da, cr, w, h| {
+                        set_draw_func[hldefs = model.hldefs.clone(), session = model.session.clone()] => move |_da, cr, w, h| {
                             let hldefs = hldefs.read();
                             let default_colors = hldefs.defaults().unwrap();
                             log::debug!("drawing default background {}x{}.", w, h);
                             if let Some(bg) = default_colors.background {
+                                let transparency = session.settings.get::<GridSettings>().transparency;
                                 cr.rectangle(0., 0., w.into(), h.into());
-                                cr.set_source_rgb(bg.red() as _, bg.green() as _, bg.blue() as _);
+                                cr.set_source_rgba(bg.red() as _, bg.green() as _, bg.blue() as _, transparency);
                                 cr.paint().unwrap();
                             }
                         }
@@ -918,24 +1899,71 @@ impl Widgets<AppModel, ()> for AppWidgets {
 
     additional_fields! {
         pointer_animation: adw::TimedAnimation,
+        preedit: gtk::DrawingArea,
+        config_monitor: Option<gtk::gio::FileMonitor>,
     }
 
     fn post_init() {
+        let cursor_widget = model.cursor.root_widget().clone();
+        model.device_scale.set(metrics::surface_scale(&main_window));
         model.calculate();
         model.gtksettings.set(overlay.settings()).ok();
         let metrics = model.metrics.get();
-        let rows = (model.opts.height as f64 / metrics.height()).ceil() as i64;
-        let cols = (model.opts.width as f64 / metrics.width()).ceil() as i64;
+        let (cols, rows) = match model.opts.geometry {
+            Some(geometry) => {
+                let width = (geometry.cols as f64 * metrics.width()).ceil() as i32;
+                let height = (geometry.rows as f64 * metrics.height()).ceil() as i32;
+                main_window.set_default_size(width, height);
+                (geometry.cols, geometry.rows)
+            }
+            None => (
+                (model.default_width as f64 / metrics.width()).ceil() as i64,
+                (model.default_height as f64 / metrics.height()).ceil() as i64,
+            ),
+        };
         let mut opts = model.opts.clone();
         opts.size.replace((cols, rows));
-        model.rt.spawn(bridge::open(opts));
+        main_window.set_decorated(!model.opts.no_decorations);
+        if model.maximized {
+            main_window.maximize();
+        }
+        if model.session.settings.get::<WindowSettings>().fullscreen {
+            match model
+                .monitor
+                .as_deref()
+                .and_then(|connector| find_monitor(&main_window.display(), connector))
+            {
+                Some(monitor) => main_window.fullscreen_on_monitor(&monitor),
+                None => main_window.fullscreen(),
+            }
+        }
+        model.window.set(main_window.clone()).ok();
+        // fullscreen from the window manager or F11 is reported to `g:reovim_fullscreen`.
+        main_window.connect_fullscreened_notify(
+            glib::clone!(@strong sender, @strong model.session as session => move |window| {
+                let fullscreen = window.is_fullscreen();
+                let mut settings = session.settings.get::<WindowSettings>();
+                settings.fullscreen = fullscreen;
+                session.settings.set(&settings);
+                sender
+                    .send(
+                        UiCommand::Parallel(ParallelCommand::SetSetting {
+                            name: "fullscreen".to_string(),
+                            value: nvim::Value::from(fullscreen),
+                        })
+                        .into(),
+                    )
+                    .unwrap();
+            }),
+        );
+        model.rt.spawn(bridge::open(opts, model.session.clone()));
         da.queue_allocate();
         da.queue_resize();
         da.queue_draw();
 
         let target = adw::CallbackAnimationTarget::new(Some(Box::new(
-            glib::clone!(@weak main_window => move |_| {
-                main_window.set_cursor_from_name(Some("text"));
+            glib::clone!(@weak main_window, @strong model.pointer as pointer => move |_| {
+                main_window.set_cursor_from_name(Some(pointer.get()));
             }),
         )));
         let pointer_animation = adw::TimedAnimation::new(&main_window, 0., 1., 1000, &target);
@@ -945,22 +1973,128 @@ impl Widgets<AppModel, ()> for AppWidgets {
             this.widget().set_cursor_from_name(Some("none"));
         });
 
+        // reloads the config file when it is saved, editors that save by renaming a new file
+        // over it show up as created.
+        let config_monitor = crate::config::path().and_then(|path| {
+            gtk::gio::File::for_path(&path)
+                .monitor_file(
+                    gtk::gio::FileMonitorFlags::NONE,
+                    gtk::gio::Cancellable::NONE,
+                )
+                .map_err(|err| log::warn!("cannot watch {}: {}", path.display(), err))
+                .ok()
+        });
+        if let Some(ref monitor) = config_monitor {
+            monitor.connect_changed(glib::clone!(@strong sender => move |_, _, _, event| {
+                if !matches!(
+                    event,
+                    gtk::gio::FileMonitorEvent::ChangesDoneHint
+                        | gtk::gio::FileMonitorEvent::Created
+                        | gtk::gio::FileMonitorEvent::Deleted
+                ) {
+                    return;
+                }
+                match Config::load() {
+                    Ok(config) => sender.send(AppMessage::ConfigChanged(Box::new(config))).unwrap(),
+                    Err(err) => sender
+                        .send(
+                            UiCommand::Parallel(ParallelCommand::ErrWriteln(format!(
+                                "Invalid config: {}",
+                                err
+                            )))
+                            .into(),
+                        )
+                        .unwrap(),
+                }
+            }));
+        }
+
+        // files from `rv --single-instance`, while this window is the active one.
+        let open_files = gtk::gio::SimpleAction::new(
+            instance::OPEN_FILES_ACTION,
+            Some(glib::VariantTy::new("(sas)").unwrap()),
+        );
+        open_files.connect_activate(glib::clone!(@strong sender => move |_, parameter| {
+            match parameter.and_then(|parameter| parameter.get::<(String, Vec<String>)>()) {
+                Some((cwd, files)) => sender.send(AppMessage::OpenFiles { cwd, files }).unwrap(),
+                None => log::warn!("invalid {} parameter: {:?}", instance::OPEN_FILES_ACTION, parameter),
+            }
+        }));
+        main_window.add_action(&open_files);
+
         let im_context = gtk::IMMulticontext::new();
-        im_context.set_use_preedit(false);
+        im_context.set_use_preedit(true);
         im_context.set_client_widget(Some(&overlay));
 
         im_context.set_input_purpose(gtk::InputPurpose::Terminal);
 
         im_context.set_cursor_location(&gdk::Rectangle::new(0, 0, 5, 10));
-        // im_context.connect_preedit_start(|_| {
-        //     log::debug!("preedit started.");
-        // });
-        // im_context.connect_preedit_end(|im_context| {
-        //     log::debug!("preedit done, '{}'", im_context.preedit_string().0);
-        // });
-        // im_context.connect_preedit_changed(|im_context| {
-        //     log::debug!("preedit changed, '{}'", im_context.preedit_string().0);
-        // });
+
+        let preedit_state = Rc::new(RefCell::new(Preedit::default()));
+        let preedit = gtk::DrawingArea::new();
+        preedit.set_widget_name("preedit");
+        preedit.set_visible(false);
+        preedit.set_can_target(false);
+        preedit.set_can_focus(false);
+        preedit.set_halign(gtk::Align::Start);
+        preedit.set_valign(gtk::Align::Start);
+        preedit.set_draw_func(glib::clone!(@strong preedit_state, @strong model.pctx as pctx, @strong model.hldefs as hldefs => move |_, cr, w, h| {
+            let preedit = preedit_state.borrow();
+            let hldefs = hldefs.read();
+            let default_colors = hldefs.defaults().unwrap();
+            if let Some(bg) = default_colors.background {
+                cr.rectangle(0., 0., w.into(), h.into());
+                cr.set_source_rgb(bg.red() as _, bg.green() as _, bg.blue() as _);
+                cr.fill().ok();
+            }
+            let fg = default_colors.foreground.unwrap_or(gdk::RGBA::WHITE);
+            cr.set_source_rgb(fg.red() as _, fg.green() as _, fg.blue() as _);
+            let layout = preedit.layout(&pctx);
+            pangocairo::update_layout(cr, &layout);
+            cr.move_to(0., 0.);
+            pangocairo::show_layout(cr, &layout);
+            // the input method cursor.
+            let pos = layout.index_to_pos(preedit.cursor_index() as i32);
+            cr.rectangle(
+                pos.x() as f64 / pango::SCALE as f64,
+                pos.y() as f64 / pango::SCALE as f64,
+                1.,
+                pos.height() as f64 / pango::SCALE as f64,
+            );
+            cr.fill().ok();
+        }));
+        overlay.add_overlay(&preedit);
+
+        im_context.connect_preedit_changed(
+            glib::clone!(@strong preedit_state, @strong preedit, @strong model.pctx as pctx, @strong model.metrics as metrics, @strong cursor_widget => move |ctx| {
+                let (text, attrs, cursor) = ctx.preedit_string();
+                log::debug!("preedit changed, '{}' cursor at {}", text, cursor);
+                preedit_state.replace(Preedit {
+                    text: text.to_string(),
+                    attrs: Some(attrs),
+                    cursor: cursor.max(0) as usize,
+                });
+                if text.is_empty() {
+                    preedit.set_visible(false);
+                    cursor_widget.set_visible(true);
+                    return;
+                }
+                let (width, height) = preedit_state.borrow().layout(&pctx).pixel_size();
+                preedit.set_content_width(width + 1);
+                preedit.set_content_height(height.max(metrics.get().height() as i32));
+                preedit.set_visible(true);
+                preedit.queue_draw();
+                cursor_widget.set_visible(false);
+            }),
+        );
+        im_context.connect_preedit_end(
+            glib::clone!(@strong preedit_state, @strong preedit, @strong cursor_widget => move |_| {
+                log::debug!("preedit done.");
+                preedit_state.replace(Preedit::default());
+                preedit.set_visible(false);
+                cursor_widget.set_visible(true);
+            }),
+        );
 
         im_context.connect_commit(glib::clone!(@strong sender => move |ctx, text| {
             log::debug!("im-context({}) commit '{}'", ctx.context_id(), text);
@@ -969,44 +2103,158 @@ impl Widgets<AppModel, ()> for AppWidgets {
                 .unwrap();
         }));
 
+        main_window.connect_scale_factor_notify(glib::clone!(@strong sender => move |window| {
+            sender.send(AppMessage::DeviceScale(metrics::surface_scale(window))).unwrap();
+        }));
+        // the surface has its fractional scale once realized, and changes from 125% to 150%
+        // keep the integer scale factor.
+        #[cfg(feature = "v4_12")]
+        main_window.connect_realize(glib::clone!(@strong sender => move |window| {
+            sender.send(AppMessage::DeviceScale(metrics::surface_scale(window))).unwrap();
+            if let Some(surface) = window.native().and_then(|native| metrics::surface(&native)) {
+                surface.connect_notify_local(Some("scale"), glib::clone!(@strong sender, @weak window => move |_, _| {
+                    sender.send(AppMessage::DeviceScale(metrics::surface_scale(&window))).unwrap();
+                }));
+            }
+        }));
+
         main_window.set_focus_widget(Some(&overlay));
         main_window.set_default_widget(Some(&overlay));
 
         let listener = gtk::EventControllerScroll::builder()
-            .flags(gtk::EventControllerScrollFlags::all())
+            .flags(
+                gtk::EventControllerScrollFlags::BOTH_AXES
+                    | gtk::EventControllerScrollFlags::KINETIC,
+            )
             .name("vimview-scrolling-listener")
             .build();
-        listener.connect_scroll(glib::clone!(@strong sender, @strong model.mouse_on as mouse_on, @strong grids_container => move |c, x, y| {
+        let accumulator = Rc::new(Cell::new(scroll::Accumulator::default()));
+        // bumped to stop a running kinetic scroll.
+        let kinetic_generation = Rc::new(Cell::new(0u64));
+        listener.connect_scroll_begin(
+            glib::clone!(@strong accumulator, @strong kinetic_generation => move |_| {
+                kinetic_generation.set(kinetic_generation.get() + 1);
+                accumulator.set(Default::default());
+            }),
+        );
+        listener.connect_scroll(glib::clone!(@strong sender, @strong model.session as session, @strong model.mouse_on as mouse_on, @strong model.scale_factor as scale_factor, @strong model.metrics as metrics, @strong model.hovering as hovering, @strong accumulator, @strong kinetic_generation => move |c, x, y| {
+            let event = c.current_event().unwrap().downcast::<gdk::ScrollEvent>().unwrap();
+            let modifier = event.modifier_state();
+            if modifier.contains(gdk::ModifierType::CONTROL_MASK) {
+                // smooth scrolling reports fractions of a notch.
+                let scale = scale_factor.get() * SCALE_STEP.powf(-y);
+                sender.send(AppMessage::ScaleFactor(scale)).unwrap();
+                return gtk::Inhibit(true)
+            }
             if !mouse_on.load(atomic::Ordering::Relaxed) {
                 return gtk::Inhibit(false)
             }
-            let event = c.current_event().unwrap().downcast::<gdk::ScrollEvent>().unwrap();
-            let modifier = event.modifier_state();
-            let id = GridActived.load(atomic::Ordering::Relaxed);
-            let direction = match event.direction() {
-                ScrollDirection::Up => {
-                    "up"
-                },
-                    ScrollDirection::Down => {
-                    "down"
-                }
-                ScrollDirection::Left => {
-                    "left"
-                }
-                ScrollDirection::Right => {
-                    "right"
+            let hovering = match hovering.get() {
+                Some(hovering) => hovering,
+                None => return gtk::Inhibit(false),
+            };
+            kinetic_generation.set(kinetic_generation.get() + 1);
+            let touchpad = event.direction() == ScrollDirection::Smooth
+                && event.device().map_or(false, |device| {
+                    matches!(device.source(), gdk::InputSource::Touchpad | gdk::InputSource::Trackpoint)
+                });
+            // a wheel notch is one wheel event, touchpads scroll a line per cell height.
+            let (dx, dy) = if touchpad {
+                let metrics = metrics.get();
+                let multiplier = session.settings.get::<ScrollSettings>().multiplier * scroll::SMOOTH_SCROLL_PIXELS;
+                (x * multiplier / metrics.width(), y * multiplier / metrics.height())
+            } else {
+                (x, y)
+            };
+            let mut acc = accumulator.get();
+            let cells = acc.add(dx, dy);
+            accumulator.set(acc);
+            send_scroll(&sender, hovering, cells, modifier);
+            gtk::Inhibit(true)
+        }));
+        listener.connect_decelerate(glib::clone!(@strong sender, @strong model.session as session, @strong model.mouse_on as mouse_on, @strong model.metrics as metrics, @strong model.hovering as hovering, @strong accumulator, @strong kinetic_generation => move |c, vel_x, vel_y| {
+            let settings = session.settings.get::<ScrollSettings>();
+            if !settings.kinetic || !mouse_on.load(atomic::Ordering::Relaxed) {
+                return;
+            }
+            let modifier = c.current_event_state();
+            let multiplier = settings.multiplier * scroll::SMOOTH_SCROLL_PIXELS;
+            let mut kinetic = scroll::Kinetic::new(vel_x * multiplier, vel_y * multiplier);
+            let generation = kinetic_generation.get() + 1;
+            kinetic_generation.set(generation);
+            const FRAME: std::time::Duration = std::time::Duration::from_millis(16);
+            glib::timeout_add_local(FRAME, glib::clone!(@strong sender, @strong metrics, @strong hovering, @strong accumulator, @strong kinetic_generation => move || {
+                if kinetic_generation.get() != generation {
+                    return glib::Continue(false);
                 }
-                _ => {
-                    return gtk::Inhibit(false)
+                let (hovering, (px, py)) = match (hovering.get(), kinetic.step(FRAME.as_secs_f64())) {
+                    (Some(hovering), Some(travelled)) => (hovering, travelled),
+                    _ => return glib::Continue(false),
+                };
+                let metrics = metrics.get();
+                let mut acc = accumulator.get();
+                let cells = acc.add(px / metrics.width(), py / metrics.height());
+                accumulator.set(acc);
+                send_scroll(&sender, hovering, cells, modifier);
+                glib::Continue(true)
+            }));
+        }));
+
+        main_window.add_controller(&listener);
+
+        let drop_target = gtk::DropTarget::new(glib::Type::INVALID, gdk::DragAction::COPY);
+        drop_target.set_types(&[gdk::FileList::static_type(), String::static_type()]);
+        drop_target.connect_drop(glib::clone!(@strong sender, @weak overlay, @strong model.metrics as metrics => @default-return false, move |target, value, x, y| {
+            let dropped = if let Ok(files) = value.get::<gdk::FileList>() {
+                let files: Vec<String> = files
+                    .files()
+                    .iter()
+                    .filter_map(|file| {
+                        file.path()
+                            .map(|path| path.to_string_lossy().into_owned())
+                            .or_else(|| Some(file.uri().to_string()))
+                    })
+                    .collect();
+                if files.is_empty() {
+                    return false;
                 }
+                Dropped::Files(files)
+            } else if let Ok(text) = value.get::<String>() {
+                Dropped::Text(text)
+            } else {
+                return false;
             };
-            log::debug!("scrolling grid {} x: {}, y: {} {}", id, x, y, &direction);
-            let command = UiCommand::Serial(SerialCommand::Scroll { direction: direction.into(), grid_id: id, position: (0, 1), modifier });
-            sender.send(AppMessage::UiCommand(command)).unwrap();
-            gtk::Inhibit(false)
+            // the grid below the drop, if any.
+            let target_grid = overlay
+                .pick(x, y, gtk::PickFlags::DEFAULT)
+                .and_then(|widget| {
+                    std::iter::successors(Some(widget), |widget| widget.parent())
+                        .find_map(|widget| widget.downcast::<vimview::VimGridView>().ok())
+                })
+                .and_then(|view| {
+                    let (x, y) = overlay.translate_coordinates(&view, x, y)?;
+                    let metrics = metrics.get();
+                    let pos = ((x / metrics.width()).floor().max(0.) as u32, (y / metrics.height()).floor().max(0.) as u32);
+                    Some(Hovering { grid: view.property::<u64>("id"), pos })
+                });
+            let split = target.current_event_state().contains(gdk::ModifierType::SHIFT_MASK);
+            log::info!("dropped {:?} on {:?}", dropped, target_grid);
+            sender.send(AppMessage::Drop { dropped, target: target_grid, split }).unwrap();
+            true
         }));
+        overlay.add_controller(&drop_target);
 
-        main_window.add_controller(&listener);
+        let zoom = gtk::GestureZoom::new();
+        let zoom_origin = Rc::new(Cell::new(1.));
+        zoom.connect_begin(glib::clone!(@strong model.scale_factor as scale_factor, @strong zoom_origin => move |_, _| {
+            zoom_origin.set(scale_factor.get());
+        }));
+        zoom.connect_scale_changed(
+            glib::clone!(@strong sender, @strong zoom_origin => move |_, scale| {
+                sender.send(AppMessage::ScaleFactor(zoom_origin.get() * scale)).unwrap();
+            }),
+        );
+        overlay.add_controller(&zoom);
 
         let focus_controller = gtk::EventControllerFocus::builder()
             .name("vimview-focus-controller")
@@ -1032,8 +2280,20 @@ impl Widgets<AppModel, ()> for AppWidgets {
             .build();
         key_controller.set_im_context(Some(&im_context));
         key_controller.connect_key_pressed(
-            glib::clone!(@strong sender => move |c, keyval, _keycode, modifier| {
+            glib::clone!(@strong sender, @strong model.session as session => move |c, keyval, keycode, modifier| {
                 let event = c.current_event().unwrap();
+                let settings = session.settings.get::<KeyboardSettings>();
+                let keyval = if settings.latin_shortcuts {
+                    keys::latin_keyval(&c.widget().display(), keyval, keycode, modifier)
+                } else {
+                    keyval
+                };
+                let modifier = settings.modifiers(modifier);
+                if let Some(action) = (keyval, modifier).to_input().and_then(|input| settings.keymap.get(&input)) {
+                    log::debug!("keypress bound to {:?}", action);
+                    sender.send(AppMessage::Action(action.clone())).unwrap();
+                    return gtk::Inhibit(true)
+                }
 
                 if let Some(true) = c.im_context().map(|imctx|imctx.filter_keypress(&event)) {
                     log::debug!("keypress handled by im-context.");
@@ -1056,13 +2316,35 @@ impl Widgets<AppModel, ()> for AppWidgets {
     }
 
     fn pre_view() {
+        if let Ok(true) = model.fullscreen_changed.compare_exchange(
+            true,
+            false,
+            atomic::Ordering::Acquire,
+            atomic::Ordering::Relaxed,
+        ) {
+            if model.session.settings.get::<WindowSettings>().fullscreen {
+                self.main_window.fullscreen();
+            } else {
+                self.main_window.unfullscreen();
+            }
+        }
         if let Ok(true) = model.show_pointer.compare_exchange(
             true,
             false,
             atomic::Ordering::Acquire,
             atomic::Ordering::Relaxed,
         ) {
-            self.pointer_animation.play();
+            match model.session.settings.get::<PointerSettings>().hide_after {
+                Some(hide_after) => {
+                    self.pointer_animation.set_duration(hide_after);
+                    self.pointer_animation.play();
+                }
+                None => {
+                    self.pointer_animation.reset();
+                    self.main_window
+                        .set_cursor_from_name(Some(model.pointer.get()));
+                }
+            }
         }
         if let Ok(true) = model.background_changed.compare_exchange(
             true,
@@ -1070,6 +2352,12 @@ impl Widgets<AppModel, ()> for AppWidgets {
             atomic::Ordering::Acquire,
             atomic::Ordering::Relaxed,
         ) {
+            let transparent = model.session.settings.get::<GridSettings>().transparency < 1.;
+            if transparent {
+                self.main_window.add_css_class("transparent");
+            } else {
+                self.main_window.remove_css_class("transparent");
+            }
             self.da.queue_draw();
         }
         if let Ok(true) = model.cursor_coord_changed.compare_exchange(
@@ -1090,6 +2378,8 @@ impl Widgets<AppModel, ()> for AppWidgets {
                     metrics.height() as i32,
                 );
                 unsafe { model.im_context.get_unchecked() }.set_cursor_location(&rect);
+                self.preedit.set_margin_start(rect.x());
+                self.preedit.set_margin_top(rect.y());
             }
         }
         if let Ok(true) = model.font_changed.compare_exchange(
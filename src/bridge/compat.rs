@@ -0,0 +1,72 @@
+//! `g:neovide_*` settings, for configs written for Neovide.
+
+use log::warn;
+use nvim::Neovim;
+
+use crate::bridge::TxWrapper;
+use crate::session::Session;
+use crate::settings::Settings;
+
+/// Neovide variables and the reovim settings they set.
+const ALIASES: &[(&str, &str)] = &[
+    ("neovide_scale_factor", "scale_factor"),
+    ("neovide_transparency", "transparency"),
+    ("neovide_fullscreen", "fullscreen"),
+    ("neovide_input_macos_alt_is_meta", "alt_as_meta"),
+];
+
+/// Neovide variables without an equivalent, animations mostly. Gtk paces redraws itself, so the
+/// refresh rates have nothing to set either. Neovide hides the pointer on key presses, reovim's
+/// `hide_mouse_when_typing` is an idle timeout instead.
+const UNSUPPORTED: &[&str] = &[
+    "neovide_hide_mouse_when_typing",
+    "neovide_cursor_vfx_mode",
+    "neovide_cursor_vfx_opacity",
+    "neovide_cursor_vfx_particle_lifetime",
+    "neovide_cursor_vfx_particle_density",
+    "neovide_cursor_vfx_particle_speed",
+    "neovide_cursor_vfx_particle_phase",
+    "neovide_cursor_vfx_particle_curl",
+    "neovide_cursor_animation_length",
+    "neovide_cursor_trail_size",
+    "neovide_cursor_antialiasing",
+    "neovide_cursor_unfocused_outline_width",
+    "neovide_scroll_animation_length",
+    "neovide_refresh_rate",
+    "neovide_refresh_rate_idle",
+    "neovide_no_idle",
+    "neovide_remember_window_size",
+    "neovide_confirm_quit",
+    "neovide_profiler",
+    "neovide_touch_deadzone",
+    "neovide_touch_drag_timeout",
+    "neovide_underline_automatic_scaling",
+    "neovide_floating_blur_amount_x",
+    "neovide_floating_blur_amount_y",
+    "neovide_background_color",
+    "neovide_input_use_logo",
+];
+
+/// Must run before the initial values are read.
+pub fn register(settings: &Settings) {
+    for (variable, name) in ALIASES {
+        settings.set_setting_alias(variable, name);
+    }
+}
+
+/// Looks for unsupported variables set by the user config, once it is loaded.
+pub async fn check_unsupported(nvim: &Neovim<TxWrapper>, session: &Session) {
+    let mut unsupported = Vec::new();
+    for variable in UNSUPPORTED {
+        if nvim.get_var(variable).await.is_ok() {
+            unsupported.push(format!("g:{}", variable));
+        }
+    }
+    if !unsupported.is_empty() {
+        warn!(
+            "unsupported neovide settings, see :checkhealth reovim: {}",
+            unsupported.join(", ")
+        );
+    }
+    session.health.write().unsupported = unsupported;
+}
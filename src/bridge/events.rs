@@ -9,6 +9,7 @@ use nvim::Value;
 
 use crate::color::{Color, Colors};
 use crate::cursor::{CursorMode, CursorShape};
+use crate::pointer;
 use crate::style::Style;
 
 use super::TxWrapper;
@@ -129,6 +130,7 @@ pub enum GuiOption {
     GuiFontSet(String),
     GuiFontWide(String),
     LineSpace(u64),
+    MouseMoveEvent(bool),
     Pumblend(u64),
     ShowTabLine(u64),
     TermGuiColors(bool),
@@ -200,6 +202,8 @@ pub enum RedrawEvent {
         row: u64,
         column_start: u64,
         cells: Vec<GridLineCell>,
+        /// the row is soft-wrapped and goes on in the next one, sent since nvim 0.10.
+        wrap: bool,
     },
     Clear {
         grid: u64,
@@ -465,6 +469,9 @@ fn parse_mode_info_set(mode_info_set_arguments: Vec<Value>) -> Result<RedrawEven
                 "attr_id" => {
                     mode_info.style = Some(parse_u64(value)?);
                 }
+                "mouse_shape" => {
+                    mode_info.mouse_shape = pointer::from_mouse_shape(&value);
+                }
                 _ => {}
             }
         }
@@ -489,6 +496,7 @@ fn parse_option_set(option_set_arguments: Vec<Value>) -> Result<RedrawEvent> {
             "guifontset" => GuiOption::GuiFontSet(parse_string(value)?),
             "guifontwide" => GuiOption::GuiFontWide(parse_string(value)?),
             "linespace" => GuiOption::LineSpace(parse_u64(value)?),
+            "mousemoveevent" => GuiOption::MouseMoveEvent(parse_bool(value)?),
             "pumblend" => GuiOption::Pumblend(parse_u64(value)?),
             "showtabline" => GuiOption::ShowTabLine(parse_u64(value)?),
             "termguicolors" => GuiOption::TermGuiColors(parse_bool(value)?),
@@ -562,6 +570,11 @@ fn parse_style(style_map: Value) -> Result<Style> {
                 }
                 ("underline", Value::Boolean(underline)) => style.underline = underline,
                 ("undercurl", Value::Boolean(undercurl)) => style.undercurl = undercurl,
+                ("underdouble", Value::Boolean(underdouble)) => style.underdouble = underdouble,
+                ("underdotted", Value::Boolean(underdotted)) => style.underdotted = underdotted,
+                ("underdashed", Value::Boolean(underdashed)) => style.underdashed = underdashed,
+                ("nocombine", Value::Boolean(nocombine)) => style.nocombine = nocombine,
+                ("url", Value::String(url)) => style.url = url.into_str(),
                 ("blend", Value::Integer(blend)) => style.blend = blend.as_u64().unwrap() as u8,
                 _ => debug!("Ignored style attribute: {}", name),
             }
@@ -623,7 +636,8 @@ fn parse_grid_line_cell(grid_line_cell: Value) -> Result<GridLineCell> {
 }
 
 fn parse_grid_line(grid_line_arguments: Vec<Value>) -> Result<RedrawEvent> {
-    let [grid_id, row, column_start, cells] = extract_values(grid_line_arguments)?;
+    let ([grid_id, row, column_start, cells], [wrap]) =
+        extract_values_with_optional(grid_line_arguments)?;
 
     let grid = parse_u64(grid_id)?;
     let row = parse_u64(row)?;
@@ -648,11 +662,16 @@ fn parse_grid_line(grid_line_arguments: Vec<Value>) -> Result<RedrawEvent> {
         cell.hldef.replace(hldef.unwrap());
         cell.double_width = double_width;
     }
+    let wrap = match wrap {
+        Some(wrap) => parse_bool(wrap)?,
+        None => false,
+    };
     Ok(RedrawEvent::GridLine {
         grid,
         row,
         column_start,
         cells,
+        wrap,
     })
 }
 
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use log::trace;
 use nvim::{Handler, Neovim, Value};
@@ -7,17 +9,17 @@ use nvim::{Handler, Neovim, Value};
 use crate::bridge::ui_commands::{ParallelCommand, UiCommand};
 use crate::{
     bridge::{events::parse_redraw_event, TxWrapper},
-    event_aggregator::EVENT_AGGREGATOR,
-    running_tracker::*,
-    settings::SETTINGS,
+    session::Session,
 };
 
 #[derive(Clone)]
-pub struct NeovimHandler {}
+pub struct NeovimHandler {
+    session: Arc<Session>,
+}
 
 impl NeovimHandler {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(session: Arc<Session>) -> Self {
+        Self { session }
     }
 }
 
@@ -29,7 +31,7 @@ impl Handler for NeovimHandler {
         &self,
         event_name: String,
         _arguments: Vec<Value>,
-        _neovim: Neovim<TxWrapper>,
+        neovim: Neovim<TxWrapper>,
     ) -> Result<Value, Value> {
         trace!("Neovim request: {:?}", &event_name);
 
@@ -49,6 +51,16 @@ impl Handler for NeovimHandler {
                 //     .map_err(|_| Value::from("cannot get remote clipboard content"))
                 Err(Value::from("get remote clipboard ignored."))
             }
+            "reovim.health" => {
+                // nvim answers requests while waiting for this one.
+                let provider = neovim
+                    .call_function("provider#clipboard#Executable", vec![])
+                    .await
+                    .ok()
+                    .and_then(|provider| provider.as_str().map(String::from))
+                    .unwrap_or_default();
+                Ok(self.session.health.read().to_value(&provider))
+            }
             _ => Ok(Value::from("rpcrequest not handled")),
         }
     }
@@ -61,7 +73,7 @@ impl Handler for NeovimHandler {
     ) {
         trace!("Neovim notification: {:?}", &event_name);
 
-        let running_tracker = RUNNING_TRACKER.clone();
+        let running_tracker = &self.session.running_tracker;
         match event_name.as_ref() {
             "redraw" => {
                 for events in arguments {
@@ -69,12 +81,12 @@ impl Handler for NeovimHandler {
                         .expect("Could not parse event from neovim");
 
                     for parsed_event in parsed_events {
-                        EVENT_AGGREGATOR.send(parsed_event);
+                        self.session.events.send(parsed_event);
                     }
                 }
             }
             "setting_changed" => {
-                SETTINGS.handle_changed_notification(arguments);
+                self.session.settings.handle_changed_notification(arguments);
             }
             "neovide.quit" => {
                 let error_code = arguments[0]
@@ -84,11 +96,15 @@ impl Handler for NeovimHandler {
             }
             #[cfg(windows)]
             "neovide.register_right_click" => {
-                EVENT_AGGREGATOR.send(UiCommand::Parallel(ParallelCommand::RegisterRightClick));
+                self.session
+                    .events
+                    .send(UiCommand::Parallel(ParallelCommand::RegisterRightClick));
             }
             #[cfg(windows)]
             "neovide.unregister_right_click" => {
-                EVENT_AGGREGATOR.send(UiCommand::Parallel(ParallelCommand::UnregisterRightClick));
+                self.session
+                    .events
+                    .send(UiCommand::Parallel(ParallelCommand::UnregisterRightClick));
             }
             "neovide.set_clipboard" => {
                 // set_remote_clipboard(arguments).ok();
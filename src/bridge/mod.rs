@@ -1,5 +1,6 @@
 // mod clipboard;
 mod command;
+mod compat;
 pub mod create;
 mod events;
 mod handler;
@@ -10,21 +11,25 @@ mod ui_commands;
 use std::sync::Arc;
 
 use log::{error, info};
-use nvim::UiAttachOptions;
+use nvim::{Neovim, UiAttachOptions, Value};
 
-use crate::{running_tracker::*, settings::*, ConnectionMode, Opts};
+use crate::{session::Session, ConnectionMode, Opts};
 
 pub use command::create_nvim_command;
 pub use events::*;
 use handler::NeovimHandler;
-use setup::setup_neovide_specific_state;
+use setup::{setup_health_check, setup_neovide_specific_state};
 pub use tx_wrapper::{TxWrapper, WrapTx};
 pub use ui_commands::{
     start_ui_command_handler, MouseAction, MouseButton, ParallelCommand, SerialCommand, UiCommand,
 };
 
-pub async fn open(opts: Opts) {
-    let handler = NeovimHandler::new();
+pub async fn open(opts: Opts, session: Arc<Session>) {
+    let handler = NeovimHandler::new(session.clone());
+    session.health.write().connection = match opts.connection_mode() {
+        ConnectionMode::Child => String::from("child"),
+        ConnectionMode::RemoteTcp(ref address) => format!("tcp {}", address),
+    };
     let (nvim, io_handler) = match opts.connection_mode() {
         ConnectionMode::Child => {
             create::new_child_cmd(&mut create_nvim_command(&opts), handler).await
@@ -38,7 +43,8 @@ pub async fn open(opts: Opts) {
         Ok("1") => {} // This is just a guard
         _ => {
             error!("Neovide requires nvim version 0.6 or higher. Download the latest version here https://github.com/neovim/neovim/wiki/Installing-Neovim");
-            std::process::exit(0);
+            session.running_tracker.quit("neovim is too old");
+            return;
         }
     }
 
@@ -51,7 +57,8 @@ pub async fn open(opts: Opts) {
     if let ConnectionMode::RemoteTcp(_) = opts.connection_mode() {
         is_remote = true;
     }
-    setup_neovide_specific_state(&nvim, is_remote).await;
+    setup_neovide_specific_state(&nvim, is_remote, &session).await;
+    setup_health_check(&nvim).await;
 
     let mut options = UiAttachOptions::new();
     options
@@ -68,14 +75,17 @@ pub async fn open(opts: Opts) {
         .expect("Could not attach ui to neovim process");
 
     info!("Neovim process attached");
+    record_extensions(&nvim, &session).await;
 
     let nvim = Arc::new(nvim);
 
-    start_ui_command_handler(nvim.clone());
-    SETTINGS.read_initial_values(&nvim).await;
-    SETTINGS.setup_changed_listeners(&nvim).await;
+    start_ui_command_handler(nvim.clone(), session.clone());
+    compat::register(&session.settings);
+    session.settings.read_initial_values(&nvim).await;
+    compat::check_unsupported(&nvim, &session).await;
+    session.settings.setup_changed_listeners(&nvim).await;
 
-    let running_tracker = RUNNING_TRACKER.clone();
+    let running_tracker = &session.running_tracker;
     tokio::select! {
         r = io_handler => {
             match r {
@@ -94,3 +104,37 @@ pub async fn open(opts: Opts) {
         }
     }
 }
+
+/// The ui extensions nvim agreed on, from `nvim_list_uis()`.
+async fn record_extensions(nvim: &Neovim<TxWrapper>, session: &Session) {
+    let uis = match nvim.list_uis().await {
+        Ok(uis) => uis,
+        Err(err) => {
+            error!("Could not list uis: {}", err);
+            return;
+        }
+    };
+    let channel = session.health.read().channel;
+    // `chan` is only listed since nvim 0.7.
+    let ui = uis
+        .iter()
+        .find(|ui| {
+            ui.as_map()
+                .and_then(|ui| ui.iter().find(|(k, _)| k.as_str() == Some("chan")))
+                .and_then(|(_, chan)| chan.as_u64())
+                .map_or(false, |chan| Some(chan) == channel)
+        })
+        .or_else(|| uis.first());
+    let extensions = ui
+        .and_then(Value::as_map)
+        .map(|ui| {
+            ui.iter()
+                .filter(|(_, enabled)| enabled.as_bool() == Some(true))
+                .filter_map(|(name, _)| name.as_str())
+                .filter(|name| name.starts_with("ext_") || *name == "rgb")
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    session.health.write().extensions = extensions;
+}
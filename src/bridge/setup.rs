@@ -2,8 +2,13 @@ use log::{info, warn};
 use nvim::{Neovim, Value};
 
 use crate::bridge::{events::*, TxWrapper};
+use crate::session::Session;
 
-pub async fn setup_neovide_remote_clipboard(nvim: &Neovim<TxWrapper>, neovide_channel: u64) {
+pub async fn setup_neovide_remote_clipboard(
+    nvim: &Neovim<TxWrapper>,
+    neovide_channel: u64,
+    session: &Session,
+) {
     // users can opt-out with
     // vim: `let g:neovide_no_custom_clipboard = v:true`
     // lua: `vim.g.neovide_no_custom_clipboard = true`
@@ -14,6 +19,9 @@ pub async fn setup_neovide_remote_clipboard(nvim: &Neovim<TxWrapper>, neovide_ch
         .and_then(|v| v.as_bool());
     if Some(true) == no_custom_clipboard {
         info!("Neovide working remotely but custom clipboard is disabled");
+        session.health.write().clipboard = String::from(
+            "remote nvim, g:neovide_no_custom_clipboard is set, nvim's provider is used",
+        );
         return;
     }
 
@@ -41,9 +49,33 @@ pub async fn setup_neovide_remote_clipboard(nvim: &Neovim<TxWrapper>, neovide_ch
     .replace('\n', "") // make one-liner, because multiline is not accepted (?)
     .replace("neovide_channel", &neovide_channel.to_string());
     nvim.command(&custom_clipboard).await.ok();
+    session.health.write().clipboard = String::from(
+        "remote nvim, g:clipboard goes through the gui, which does not support it yet",
+    );
+}
+
+/// Writes `:checkhealth reovim` to the cache of nvim, which may run elsewhere, and adds it to
+/// `runtimepath`.
+pub async fn setup_health_check(nvim: &Neovim<TxWrapper>) {
+    let install = r#"
+        local dir = vim.fn.stdpath('cache') .. '/reovim/runtime'
+        vim.fn.mkdir(dir .. '/lua/reovim', 'p')
+        local file = assert(io.open(dir .. '/lua/reovim/health.lua', 'w'))
+        file:write(...)
+        file:close()
+        vim.opt.runtimepath:append(dir)
+    "#;
+    let health = include_str!("../../runtime/lua/reovim/health.lua");
+    if let Err(err) = nvim.exec_lua(install, vec![Value::from(health)]).await {
+        warn!("Could not install the health check: {}", err);
+    }
 }
 
-pub async fn setup_neovide_specific_state(nvim: &Neovim<TxWrapper>, is_remote: bool) {
+pub async fn setup_neovide_specific_state(
+    nvim: &Neovim<TxWrapper>,
+    is_remote: bool,
+    session: &Session,
+) {
     // Set variable indicating to user config that neovide is being used
     nvim.set_var("neovide", Value::Boolean(true))
         .await
@@ -101,6 +133,7 @@ pub async fn setup_neovide_specific_state(nvim: &Neovim<TxWrapper>, is_remote: b
             "Neovide registered to nvim with channel id {}",
             neovide_channel
         );
+        session.health.write().channel.replace(neovide_channel);
 
         // Create a command for registering right click context hooking
         #[cfg(windows)]
@@ -125,12 +158,18 @@ pub async fn setup_neovide_specific_state(nvim: &Neovim<TxWrapper>, is_remote: b
         .ok();
 
         if is_remote {
-            setup_neovide_remote_clipboard(nvim, neovide_channel).await;
+            setup_neovide_remote_clipboard(nvim, neovide_channel, session).await;
         }
     } else {
         warn!("Neovide could not find the correct channel id. Some functionality may be disabled.");
     }
 
+    if !is_remote {
+        session.health.write().clipboard = String::from(
+            "Ctrl+Shift+C and Ctrl+Shift+V use the gui clipboard, registers use nvim's provider",
+        );
+    }
+
     // Set some basic rendering options
     nvim.set_option("lazyredraw", Value::Boolean(false))
         .await
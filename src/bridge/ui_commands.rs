@@ -1,7 +1,7 @@
 use std::ops::Deref;
 use std::sync::Arc;
 
-use nvim::{call_args, rpc::model::IntoVal, Neovim};
+use nvim::{call_args, rpc::model::IntoVal, Neovim, Value, Window};
 use tokio::sync::mpsc::unbounded_channel;
 
 #[cfg(windows)]
@@ -9,8 +9,10 @@ use crate::windows_utils::{
     register_rightclick_directory, register_rightclick_file, unregister_rightclick,
 };
 use crate::{
-    bridge::TxWrapper, event_aggregator::EVENT_AGGREGATOR, keys::ToInput,
-    running_tracker::RUNNING_TRACKER,
+    bridge::TxWrapper,
+    keys::{KeyboardSettings, ToInput},
+    session::Session,
+    settings::{self, Settings},
 };
 
 #[derive(Clone, Copy, Debug)]
@@ -107,13 +109,24 @@ impl std::fmt::Display for MouseButton {
 #[derive(Clone, Debug)]
 pub enum SerialCommand {
     Keyboard(String),
+    /// Yank the visual selection to the `+` register.
+    Copy,
+    Paste(String),
     MouseButton {
         action: MouseAction,
         button: MouseButton,
         modifier: gtk::gdk::ModifierType,
+        /// 2 and 3 for double and triple clicks.
+        clicks: i32,
         grid_id: u64,
         position: (u32, u32),
     },
+    /// Pointer motion, sent when `'mousemoveevent'` is set.
+    MouseMove {
+        grid_id: u64,
+        position: (u32, u32),
+        modifier: gtk::gdk::ModifierType,
+    },
     Scroll {
         direction: String,
         grid_id: u64,
@@ -129,22 +142,45 @@ pub enum SerialCommand {
 }
 
 impl SerialCommand {
-    async fn execute(self, nvim: &Neovim<TxWrapper>) {
+    async fn execute(self, nvim: &Neovim<TxWrapper>, settings: &Settings) {
         match self {
             SerialCommand::Keyboard(input_command) => {
                 log::trace!("Keyboard Input Sent: {}", input_command);
                 nvim.input(&input_command).await.expect("Input failed");
             }
+            SerialCommand::Copy => {
+                let mode = nvim.get_mode().await.unwrap_or_default();
+                let visual = mode.iter().any(|(key, value)| {
+                    key.as_str() == Some("mode")
+                        && matches!(
+                            value.as_str().and_then(|mode| mode.chars().next()),
+                            Some('v' | 'V' | '\x16')
+                        )
+                });
+                if visual {
+                    nvim.input("\"+y").await.expect("Input failed");
+                }
+            }
+            SerialCommand::Paste(text) => {
+                log::trace!("Paste {} bytes", text.len());
+                for (chunk, phase) in paste_chunks(&text, PASTE_CHUNK_SIZE) {
+                    // false when the paste was cancelled, by <Esc> for example.
+                    if !nvim.paste(chunk, true, phase).await.unwrap_or(false) {
+                        break;
+                    }
+                }
+            }
             SerialCommand::MouseButton {
                 action,
                 button,
                 modifier,
+                clicks,
                 grid_id,
                 position: (grid_x, grid_y),
             } => {
                 let action: &str = &action;
                 let button: &str = &button;
-                let modifier: &str = &modifier.to_input().unwrap();
+                let modifier: &str = &mouse_modifier(settings, modifier, clicks);
                 log::trace!(
                     "input mouse button='{}' action='{}' modifier='{}' {}<({}, {})>",
                     button,
@@ -157,7 +193,7 @@ impl SerialCommand {
                 nvim.input_mouse(
                     button,
                     action,
-                    "",
+                    modifier,
                     grid_id as i64,
                     grid_y as i64,
                     grid_x as i64,
@@ -176,12 +212,12 @@ impl SerialCommand {
                     direction,
                     grid_id,
                     (grid_x, grid_y),
-                    AsRef::<str>::as_ref(&modifier.to_input().unwrap()),
+                    mouse_modifier(settings, modifier, 1),
                 );
                 nvim.input_mouse(
                     "wheel",
                     &direction,
-                    &modifier.to_input().unwrap(),
+                    &mouse_modifier(settings, modifier, 1),
                     grid_id as i64,
                     grid_y as i64,
                     grid_x as i64,
@@ -198,7 +234,7 @@ impl SerialCommand {
                 nvim.input_mouse(
                     &button,
                     "drag",
-                    &modifier.to_input().unwrap(),
+                    &mouse_modifier(settings, modifier, 1),
                     grid_id as i64,
                     grid_y as i64,
                     grid_x as i64,
@@ -206,10 +242,80 @@ impl SerialCommand {
                 .await
                 .expect("Mouse Drag Failed");
             }
+            SerialCommand::MouseMove {
+                grid_id,
+                position: (grid_x, grid_y),
+                modifier,
+            } => {
+                nvim.input_mouse(
+                    "move",
+                    "",
+                    &mouse_modifier(settings, modifier, 1),
+                    grid_id as i64,
+                    grid_y as i64,
+                    grid_x as i64,
+                )
+                .await
+                .ok();
+            }
         }
     }
 }
 
+/// Bytes per `nvim_paste` call, nvim redraws between the chunks of a large paste.
+const PASTE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Splits a paste into chunks with the phase of `nvim_paste`: -1 for a single chunk, else 1
+/// for the first, 2 for the ones in the middle and 3 for the last.
+fn paste_chunks(text: &str, size: usize) -> impl Iterator<Item = (&str, i64)> {
+    let mut rest = text;
+    let mut first = true;
+    std::iter::from_fn(move || {
+        if rest.is_empty() && !first {
+            return None;
+        }
+        let mut end = size.min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end += 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        rest = tail;
+        let phase = match (first, rest.is_empty()) {
+            (true, true) => -1,
+            (true, false) => 1,
+            (false, false) => 2,
+            (false, true) => 3,
+        };
+        first = false;
+        Some((chunk, phase))
+    })
+}
+
+/// Modifiers of a mouse event for `nvim_input_mouse`, multiple clicks are a modifier too.
+fn mouse_modifier(settings: &Settings, modifier: gtk::gdk::ModifierType, clicks: i32) -> String {
+    let modifier = settings
+        .get::<KeyboardSettings>()
+        .modifiers(modifier)
+        .to_input()
+        .unwrap();
+    match clicks {
+        2..=4 => format!("{}-{}", clicks, modifier),
+        _ => modifier,
+    }
+}
+
+/// File names escaped for an Ex command line, those nvim could not escape are left out.
+async fn escape_files(nvim: &Neovim<TxWrapper>, files: Vec<String>) -> Vec<String> {
+    let mut escaped = Vec::with_capacity(files.len());
+    for file in files {
+        if let Ok(Value::String(file)) = nvim.call_function("fnameescape", vec![file.into()]).await
+        {
+            escaped.push(file.into_str().unwrap_or_default());
+        }
+    }
+    escaped
+}
+
 #[derive(Debug, Clone)]
 pub enum ParallelCommand {
     Quit,
@@ -217,7 +323,21 @@ pub enum ParallelCommand {
         width: u64,
         height: u64,
     },
-    FileDrop(String),
+    /// Open dropped files with a single `:drop`, or `:split` each, in the window they were
+    /// dropped on.
+    FileDrop {
+        files: Vec<String>,
+        window: Option<Value>,
+        split: bool,
+    },
+    /// Open files forwarded by `rv --single-instance` with `:tab drop`.
+    OpenFiles(Vec<String>),
+    ErrWriteln(String),
+    /// Sync a setting changed on the gui side back to `g:reovim_{name}`.
+    SetSetting {
+        name: String,
+        value: Value,
+    },
     FocusLost,
     FocusGained,
     DisplayAvailableFonts(Vec<String>),
@@ -245,8 +365,48 @@ impl ParallelCommand {
                 .command("if exists('#FocusGained') | doautocmd <nomodeline> FocusGained | endif")
                 .await
                 .expect("Focus Gained Failed"),
-            ParallelCommand::FileDrop(path) => {
-                nvim.command(format!("e {}", path).as_str()).await.ok();
+            ParallelCommand::FileDrop {
+                files,
+                window,
+                split,
+            } => {
+                if let Some(window) = window {
+                    let window = Window::new(window, nvim.clone());
+                    nvim.set_current_win(&window).await.ok();
+                }
+                let escaped = escape_files(nvim, files).await;
+                if escaped.is_empty() {
+                    return;
+                }
+                if split {
+                    for file in escaped {
+                        nvim.command(&format!("split {}", file)).await.ok();
+                    }
+                } else {
+                    // one `:drop`, the first file goes to the target window, or to the window it
+                    // is already shown in, the others to the argument list.
+                    nvim.command(&format!("drop {}", escaped.join(" ")))
+                        .await
+                        .ok();
+                }
+            }
+            ParallelCommand::OpenFiles(files) => {
+                // the first file is shown in a new tab, or where it is open already, the others
+                // are in the argument list.
+                let escaped = escape_files(nvim, files).await;
+                if !escaped.is_empty() {
+                    nvim.command(&format!("tab drop {}", escaped.join(" ")))
+                        .await
+                        .ok();
+                }
+            }
+            ParallelCommand::ErrWriteln(message) => {
+                nvim.err_writeln(&message).await.ok();
+            }
+            ParallelCommand::SetSetting { name, value } => {
+                nvim.set_var(&settings::variable_name(&name), value)
+                    .await
+                    .ok();
             }
             ParallelCommand::DisplayAvailableFonts(fonts) => {
                 let mut content: Vec<String> = vec![
@@ -341,12 +501,13 @@ impl From<ParallelCommand> for UiCommand {
     }
 }
 
-pub fn start_ui_command_handler(nvim: Arc<Neovim<TxWrapper>>) {
+pub fn start_ui_command_handler(nvim: Arc<Neovim<TxWrapper>>, session: Arc<Session>) {
     let (serial_tx, mut serial_rx) = unbounded_channel::<SerialCommand>();
     let ui_command_nvim = nvim.clone();
-    let running_tracker = RUNNING_TRACKER.clone();
+    let ui_command_session = session.clone();
     tokio::spawn(async move {
-        let mut ui_command_receiver = EVENT_AGGREGATOR.register_event::<UiCommand>();
+        let running_tracker = &ui_command_session.running_tracker;
+        let mut ui_command_receiver = ui_command_session.events.register_event::<UiCommand>();
         loop {
             tokio::select! {
                 _ = running_tracker.wait_quit() => {
@@ -374,8 +535,8 @@ pub fn start_ui_command_handler(nvim: Arc<Neovim<TxWrapper>>) {
         }
     });
 
-    let running_tracker = RUNNING_TRACKER.clone();
     tokio::spawn(async move {
+        let running_tracker = &session.running_tracker;
         loop {
             tokio::select! {
                 _ =  running_tracker.wait_quit() => {
@@ -384,7 +545,7 @@ pub fn start_ui_command_handler(nvim: Arc<Neovim<TxWrapper>>) {
                 },
                 Some(serial_command) = serial_rx.recv() => {
                     log::trace!("aggregated serial ui-command");
-                    serial_command.execute(&nvim).await;
+                    serial_command.execute(&nvim, &session.settings).await;
                 },
                 else => {
                     running_tracker.quit("serial ui command channel failed");
@@ -407,3 +568,27 @@ pub fn start_ui_command_handler(nvim: Arc<Neovim<TxWrapper>>) {
         */
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paste_chunks() {
+        let chunks: Vec<_> = paste_chunks("hello", 16).collect();
+        assert_eq!(chunks, [("hello", -1)]);
+
+        let chunks: Vec<_> = paste_chunks("", 16).collect();
+        assert_eq!(chunks, [("", -1)]);
+
+        let chunks: Vec<_> = paste_chunks("abcdefgh", 3).collect();
+        assert_eq!(chunks, [("abc", 1), ("def", 2), ("gh", 3)]);
+
+        let chunks: Vec<_> = paste_chunks("abcdef", 3).collect();
+        assert_eq!(chunks, [("abc", 1), ("def", 3)]);
+
+        // chunks never split a character.
+        let chunks: Vec<_> = paste_chunks("aéé", 2).collect();
+        assert_eq!(chunks, [("aé", 1), ("é", 3)]);
+    }
+}
@@ -0,0 +1,289 @@
+use std::path::PathBuf;
+
+/// Gui defaults from `$XDG_CONFIG_HOME/reovim/config.toml`, read before the window is built.
+/// Command line options and their environment variables take precedence.
+///
+/// ```toml
+/// font = "Cascadia Code:h12"
+///
+/// [window]
+/// width = 1200
+/// height = 800
+/// maximized = false
+/// decorations = true
+/// transparency = 0.9
+///
+/// [keymap]
+/// "<D-c>" = "copy"
+///
+/// [theme]
+/// background = "#1e1e2e"
+/// foreground = "#cdd6f4"
+///
+/// [connection]
+/// nvim = "/usr/local/bin/nvim"
+/// args = ["--cmd", "let g:in_reovim = 1"]
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    /// `guifont` syntax, used until neovim sets `guifont`.
+    pub font: Option<String>,
+    pub window: WindowConfig,
+    /// bindings like `g:reovim_keymap`, which adds to them.
+    pub keymap: Vec<(String, String)>,
+    pub theme: ThemeConfig,
+    pub connection: ConnectionConfig,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WindowConfig {
+    /// size in logical pixels when there is no saved window state.
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub maximized: bool,
+    pub fullscreen: bool,
+    pub decorations: bool,
+    /// `g:reovim_transparency`.
+    pub transparency: Option<f64>,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            width: None,
+            height: None,
+            maximized: false,
+            fullscreen: false,
+            decorations: true,
+            transparency: None,
+        }
+    }
+}
+
+/// Default colors until neovim sends its own, avoids a flash of black on start.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ThemeConfig {
+    pub background: Option<String>,
+    pub foreground: Option<String>,
+    pub special: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConnectionConfig {
+    /// like `--nvim`.
+    pub nvim: Option<String>,
+    /// like `--remote`.
+    pub remote: Option<String>,
+    /// passed to nvim if none are given after `--`.
+    pub args: Vec<String>,
+}
+
+/// `$XDG_CONFIG_HOME/reovim/config.toml`.
+pub fn path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("reovim").join("config.toml"))
+}
+
+fn expect<'a, T>(
+    table: &'a toml::value::Table,
+    key: &str,
+    convert: impl Fn(&'a toml::Value) -> Option<T>,
+    expected: &str,
+) -> Result<Option<T>, String> {
+    match table.get(key) {
+        Some(value) => convert(value)
+            .map(Some)
+            .ok_or_else(|| format!("{}: expected {}, got {}", key, expected, value)),
+        None => Ok(None),
+    }
+}
+
+fn section<'a>(
+    table: &'a toml::value::Table,
+    key: &str,
+) -> Result<Option<&'a toml::value::Table>, String> {
+    expect(table, key, toml::Value::as_table, "a table")
+}
+
+fn string(value: &toml::Value) -> Option<String> {
+    value.as_str().map(str::to_string)
+}
+
+impl Config {
+    /// A missing file is the default config.
+    pub fn load() -> Result<Config, String> {
+        let path = match path() {
+            Some(path) => path,
+            None => return Ok(Config::default()),
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                Config::from_toml(&content).map_err(|err| format!("{}: {}", path.display(), err))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(format!("{}: {}", path.display(), err)),
+        }
+    }
+
+    pub fn from_toml(content: &str) -> Result<Config, String> {
+        let value = content
+            .parse::<toml::Value>()
+            .map_err(|err| err.to_string())?;
+        let table = value.as_table().ok_or("expected a table")?;
+        let mut config = Config {
+            font: expect(table, "font", string, "a string")?,
+            ..Config::default()
+        };
+
+        if let Some(window) = section(table, "window")? {
+            let size = |v: &toml::Value| {
+                v.as_integer()
+                    .filter(|v| *v > 0 && *v <= i32::MAX as i64)
+                    .map(|v| v as i32)
+            };
+            let config = &mut config.window;
+            config.width = expect(window, "width", size, "a positive integer")?;
+            config.height = expect(window, "height", size, "a positive integer")?;
+            config.maximized = expect(window, "maximized", toml::Value::as_bool, "a boolean")?
+                .unwrap_or(config.maximized);
+            config.fullscreen = expect(window, "fullscreen", toml::Value::as_bool, "a boolean")?
+                .unwrap_or(config.fullscreen);
+            config.decorations = expect(window, "decorations", toml::Value::as_bool, "a boolean")?
+                .unwrap_or(config.decorations);
+            let transparency = |v: &toml::Value| {
+                v.as_float()
+                    .or_else(|| v.as_integer().map(|v| v as f64))
+                    .filter(|v| (0. ..=1.).contains(v))
+            };
+            config.transparency =
+                expect(window, "transparency", transparency, "a number from 0 to 1")?;
+        }
+
+        if let Some(keymap) = section(table, "keymap")? {
+            for (chord, action) in keymap {
+                let action = action
+                    .as_str()
+                    .ok_or_else(|| format!("keymap: invalid binding {} = {}", chord, action))?;
+                config.keymap.push((chord.clone(), action.to_string()));
+            }
+        }
+
+        if let Some(theme) = section(table, "theme")? {
+            config.theme = ThemeConfig {
+                background: expect(theme, "background", string, "a color")?,
+                foreground: expect(theme, "foreground", string, "a color")?,
+                special: expect(theme, "special", string, "a color")?,
+            };
+        }
+
+        if let Some(connection) = section(table, "connection")? {
+            let args = |v: &toml::Value| {
+                v.as_array()?
+                    .iter()
+                    .map(|arg| arg.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
+            };
+            config.connection = ConnectionConfig {
+                nvim: expect(connection, "nvim", string, "a path")?,
+                remote: expect(connection, "remote", string, "HOST:PORT")?,
+                args: expect(connection, "args", args, "a list of strings")?.unwrap_or_default(),
+            };
+        }
+
+        Ok(config)
+    }
+
+    /// `[keymap]` in the shape of `g:reovim_keymap`.
+    pub fn keymap_value(&self) -> nvim::Value {
+        nvim::Value::Map(
+            self.keymap
+                .iter()
+                .map(|(chord, action)| (chord.as_str().into(), action.as_str().into()))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(Config::from_toml(""), Ok(Config::default()));
+    }
+
+    #[test]
+    fn test_full() {
+        let config = Config::from_toml(
+            r##"
+font = "Cascadia Code:h12"
+
+[window]
+width = 1200
+height = 800
+decorations = false
+transparency = 0.9
+
+[keymap]
+"<D-c>" = "copy"
+"<A-x>" = "<Esc>:"
+
+[theme]
+background = "#1e1e2e"
+
+[connection]
+nvim = "/usr/local/bin/nvim"
+args = ["--clean"]
+"##,
+        )
+        .unwrap();
+        assert_eq!(config.font.as_deref(), Some("Cascadia Code:h12"));
+        assert_eq!(
+            config.window,
+            WindowConfig {
+                width: Some(1200),
+                height: Some(800),
+                maximized: false,
+                fullscreen: false,
+                decorations: false,
+                transparency: Some(0.9),
+            }
+        );
+        assert_eq!(config.theme.background.as_deref(), Some("#1e1e2e"));
+        assert_eq!(config.theme.foreground, None);
+        assert_eq!(
+            config.connection.nvim.as_deref(),
+            Some("/usr/local/bin/nvim")
+        );
+        assert_eq!(config.connection.remote, None);
+        assert_eq!(config.connection.args, vec!["--clean".to_string()]);
+        assert_eq!(
+            config.keymap_value(),
+            nvim::Value::Map(vec![
+                ("<A-x>".into(), "<Esc>:".into()),
+                ("<D-c>".into(), "copy".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let cases = [
+            "font = 12",
+            "window = 1",
+            "[window]\nwidth = 0",
+            "[window]\ntransparency = 1.5",
+            "[window]\nmaximized = \"yes\"",
+            "[keymap]\n\"<D-c>\" = 1",
+            "[connection]\nargs = [1]",
+            "[window",
+        ];
+        for case in cases {
+            assert!(Config::from_toml(case).is_err(), "{}", case);
+        }
+    }
+}
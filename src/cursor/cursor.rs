@@ -1,11 +1,14 @@
 use std::cell::Cell;
 use std::rc::Rc;
+use std::sync::Arc;
 
 use parking_lot::RwLock;
 
 use crate::color::Color;
 use crate::grapheme::Coord;
 use crate::metrics::Metrics;
+use crate::session::Session;
+use crate::settings::setting_group;
 use crate::vimview::{HighlightDefinitions, TextCell};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -34,6 +37,26 @@ pub struct CursorMode {
     pub blinkwait: Option<u64>,
     pub blinkon: Option<u64>,
     pub blinkoff: Option<u64>,
+    /// css name of the mouse pointer in this mode.
+    pub mouse_shape: Option<&'static str>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CursorSettings {
+    /// follow the blinking of `guicursor`, `g:reovim_cursor_blink`.
+    pub blink: bool,
+}
+
+impl Default for CursorSettings {
+    fn default() -> Self {
+        CursorSettings { blink: true }
+    }
+}
+
+setting_group! {
+    CursorSettings {
+        "cursor_blink" => blink,
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -56,6 +79,7 @@ pub struct Cursor {
     pub pctx: Rc<pango::Context>,
     pub metrics: Rc<Cell<Metrics>>,
     pub hldefs: Rc<RwLock<HighlightDefinitions>>,
+    pub session: Arc<Session>,
 }
 
 impl Cursor {
@@ -63,6 +87,7 @@ impl Cursor {
         pctx: Rc<pango::Context>,
         metrics: Rc<Cell<Metrics>>,
         hldefs: Rc<RwLock<HighlightDefinitions>>,
+        session: Arc<Session>,
     ) -> Cursor {
         Cursor {
             grid: 0,
@@ -80,6 +105,7 @@ impl Cursor {
             pctx,
             hldefs,
             metrics,
+            session,
         }
     }
 
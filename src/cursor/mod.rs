@@ -2,7 +2,7 @@ mod cursor;
 // mod state;
 // mod vfx;
 
-pub use cursor::{Cursor as VimCursor, CursorMode, CursorShape};
+pub use cursor::{Cursor as VimCursor, CursorMode, CursorSettings, CursorShape};
 use gtk::prelude::{StyleContextExt, WidgetExt};
 
 use relm4::drawing::DrawContext;
@@ -122,6 +122,9 @@ impl MicroWidgets<VimCursor> for CursorWidgets {
 
 impl VimCursor {
     fn maybe_blinking(&self) -> Option<String> {
+        if !self.session.settings.get::<CursorSettings>().blink {
+            return None;
+        }
         let blinkon = self.blinkon().filter(|blinkon| *blinkon > 0)?;
         let blinkoff = self.blinkoff().filter(|blinkoff| *blinkoff > 0)?;
         let blinkwait = self.blinkwait().filter(|blinkwait| *blinkwait > 0)?;
@@ -4,18 +4,11 @@ use std::{
     fmt::Debug,
 };
 
-use once_cell::sync::Lazy;
 use parking_lot::{Mutex, RwLock};
 use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
 
 use crate::loggingchan::*;
 
-pub static EVENT_AGGREGATOR: Lazy<EventAggregator> = Lazy::new(|| EventAggregator::default());
-
-thread_local! {
-    static THREAD_SENDERS: RwLock<HashMap<TypeId, Box<dyn Any + Send>>> = RwLock::new(HashMap::new());
-}
-
 pub struct EventAggregator {
     parent_senders: RwLock<HashMap<TypeId, Mutex<Box<dyn Any + Send>>>>,
     unclaimed_receivers: RwLock<HashMap<TypeId, Box<dyn Any + Send + Sync>>>,
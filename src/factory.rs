@@ -159,6 +159,17 @@ where
         self.data.get_mut(&key)
     }
 
+    /// Stages an update of the widget at `key`, so that it is redrawn on the next view,
+    /// without touching its data.
+    pub fn queue_update(&mut self, key: u64) {
+        if self.data.contains_key(&key) {
+            self.staged
+                .borrow_mut()
+                .entry(key)
+                .or_insert(ChangeType::Update);
+        }
+    }
+
     pub fn flush(&mut self) {
         let mut staged = self.staged.borrow_mut();
         let mut flushes = self.flushes.borrow_mut();
@@ -0,0 +1,467 @@
+use std::fmt;
+
+use crate::settings::{setting_group, ParseFromValue};
+
+/// Size used when `guifont` does not specify `:h`.
+pub const DEFAULT_FONT_SIZE: f64 = 11.;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Antialias {
+    None,
+    Gray,
+    Subpixel,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hinting {
+    None,
+    Slight,
+    Medium,
+    Full,
+}
+
+impl Antialias {
+    /// Names of `:#e-` in `guifont`.
+    fn from_name(name: &str) -> Option<Antialias> {
+        match name {
+            "alias" => Some(Antialias::None),
+            "antialias" => Some(Antialias::Gray),
+            "subpixelantialias" => Some(Antialias::Subpixel),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Antialias::None => "alias",
+            Antialias::Gray => "antialias",
+            Antialias::Subpixel => "subpixelantialias",
+        }
+    }
+}
+
+impl Hinting {
+    /// Names of `:#h-` in `guifont`.
+    fn from_name(name: &str) -> Option<Hinting> {
+        match name {
+            "none" => Some(Hinting::None),
+            "slight" => Some(Hinting::Slight),
+            "medium" | "normal" => Some(Hinting::Medium),
+            "full" => Some(Hinting::Full),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Hinting::None => "none",
+            Hinting::Slight => "slight",
+            Hinting::Medium => "medium",
+            Hinting::Full => "full",
+        }
+    }
+}
+
+impl ParseFromValue for Antialias {
+    fn parse_from_value(value: &nvim::Value) -> Result<Self, String> {
+        value
+            .as_str()
+            .and_then(Antialias::from_name)
+            .ok_or_else(|| {
+                format!(
+                    "expected one of alias, antialias or subpixelantialias, got {}",
+                    value
+                )
+            })
+    }
+
+    fn to_value(&self) -> nvim::Value {
+        nvim::Value::from(self.name())
+    }
+}
+
+impl ParseFromValue for Hinting {
+    fn parse_from_value(value: &nvim::Value) -> Result<Self, String> {
+        value.as_str().and_then(Hinting::from_name).ok_or_else(|| {
+            format!(
+                "expected one of none, slight, medium or full, got {}",
+                value
+            )
+        })
+    }
+
+    fn to_value(&self) -> nvim::Value {
+        nvim::Value::from(self.name())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FontSettings {
+    /// font zoom, mirrors `AppModel::scale_factor` for `g:reovim_scale_factor`.
+    pub scale_factor: f64,
+    /// used when `guifont` has no `:#e-`, `g:reovim_font_antialias`.
+    pub antialias: Option<Antialias>,
+    /// used when `guifont` has no `:#h-`, `g:reovim_font_hinting`.
+    pub hinting: Option<Hinting>,
+}
+
+impl Default for FontSettings {
+    fn default() -> Self {
+        FontSettings {
+            scale_factor: 1.,
+            antialias: None,
+            hinting: None,
+        }
+    }
+}
+
+setting_group! {
+    FontSettings {
+        "scale_factor" => scale_factor if |v: &f64| *v > 0.,
+        "font_antialias" => antialias,
+        "font_hinting" => hinting,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GuiFontError {
+    Empty,
+    EmptyFamily,
+    InvalidSize(String),
+    InvalidWidth(String),
+    InvalidWeight(String),
+    InvalidAntialias(String),
+    InvalidHinting(String),
+    UnknownOption(String),
+}
+
+impl fmt::Display for GuiFontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GuiFontError::Empty => write!(f, "no font specified"),
+            GuiFontError::EmptyFamily => write!(f, "empty font name in font list"),
+            GuiFontError::InvalidSize(v) => write!(f, "invalid font size '{}'", v),
+            GuiFontError::InvalidWidth(v) => write!(f, "invalid width offset '{}'", v),
+            GuiFontError::InvalidWeight(v) => write!(f, "invalid font weight '{}'", v),
+            GuiFontError::InvalidAntialias(v) => write!(f, "invalid antialias mode '{}'", v),
+            GuiFontError::InvalidHinting(v) => write!(f, "invalid hinting mode '{}'", v),
+            GuiFontError::UnknownOption(v) => write!(f, "unknown option ':{}'", v),
+        }
+    }
+}
+
+impl std::error::Error for GuiFontError {}
+
+/// Parsed value of `guifont` or `guifontwide`.
+///
+/// The syntax is `{font}[,{font}...][:{option}...]`, options apply to the whole
+/// fallback chain:
+///
+///   - `h{size}`  font size in points, may be fractional
+///   - `w{px}`    pixels added to the cell width, may be negative
+///   - `W{n}`     numeric font weight, 100 - 1000
+///   - `b` / `i`  bold / italic
+///   - `#e-{antialias,subpixelantialias,alias}`
+///   - `#h-{none,slight,medium,full}`
+#[derive(Clone, Debug, PartialEq)]
+pub struct GuiFont {
+    /// font families ordered by priority.
+    pub families: Vec<String>,
+    pub size: Option<f64>,
+    pub width: f64,
+    pub weight: Option<u16>,
+    pub bold: bool,
+    pub italic: bool,
+    pub antialias: Option<Antialias>,
+    pub hinting: Option<Hinting>,
+}
+
+impl Default for GuiFont {
+    fn default() -> Self {
+        GuiFont {
+            families: Vec::new(),
+            size: None,
+            width: 0.,
+            weight: None,
+            bold: false,
+            italic: false,
+            antialias: None,
+            hinting: None,
+        }
+    }
+}
+
+impl GuiFont {
+    pub fn parse(guifont: &str) -> Result<GuiFont, GuiFontError> {
+        let guifont = guifont.trim();
+        if guifont.is_empty() {
+            return Err(GuiFontError::Empty);
+        }
+        let mut font = GuiFont::default();
+        for entry in split_escaped(guifont, ',') {
+            let mut parts = split_escaped(&entry, ':').into_iter();
+            let family = parts.next().unwrap_or_default();
+            let family = family.trim().replace('_', " ");
+            if family.is_empty() {
+                return Err(GuiFontError::EmptyFamily);
+            }
+            font.families.push(family);
+            for option in parts {
+                font.apply_option(option.trim())?;
+            }
+        }
+        Ok(font)
+    }
+
+    fn apply_option(&mut self, option: &str) -> Result<(), GuiFontError> {
+        if let Some(mode) = option.strip_prefix("#e-") {
+            let antialias = Antialias::from_name(mode)
+                .ok_or_else(|| GuiFontError::InvalidAntialias(mode.to_string()))?;
+            self.antialias.replace(antialias);
+            return Ok(());
+        }
+        if let Some(mode) = option.strip_prefix("#h-") {
+            let hinting = Hinting::from_name(mode)
+                .ok_or_else(|| GuiFontError::InvalidHinting(mode.to_string()))?;
+            self.hinting.replace(hinting);
+            return Ok(());
+        }
+        let mut chars = option.chars();
+        let name = chars.next();
+        let value = chars.as_str();
+        match name {
+            Some('h') => {
+                let size = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|size| *size > 0.)
+                    .ok_or_else(|| GuiFontError::InvalidSize(value.to_string()))?;
+                self.size.replace(size);
+            }
+            Some('w') => {
+                self.width = value
+                    .parse::<f64>()
+                    .map_err(|_| GuiFontError::InvalidWidth(value.to_string()))?;
+            }
+            Some('W') => {
+                let weight = value
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|weight| (100..=1000).contains(weight))
+                    .ok_or_else(|| GuiFontError::InvalidWeight(value.to_string()))?;
+                self.weight.replace(weight);
+            }
+            Some('b') if value.is_empty() => self.bold = true,
+            Some('i') if value.is_empty() => self.italic = true,
+            // charset and quality only make sense on windows.
+            Some('c') | Some('q') => {}
+            _ => return Err(GuiFontError::UnknownOption(option.to_string())),
+        }
+        Ok(())
+    }
+
+    pub fn size(&self) -> f64 {
+        self.size.unwrap_or(DEFAULT_FONT_SIZE)
+    }
+
+    /// Families joined as a pango fallback list.
+    pub fn family(&self) -> String {
+        self.families.join(",")
+    }
+
+    pub fn font_description(&self) -> pango::FontDescription {
+        let mut desc = pango::FontDescription::new();
+        desc.set_family(&self.family());
+        desc.set_size((self.size() * pango::SCALE as f64).round() as i32);
+        if let Some(weight) = self.weight {
+            desc.set_weight(weight_from_u16(weight));
+        } else if self.bold {
+            desc.set_weight(pango::Weight::Bold);
+        }
+        if self.italic {
+            desc.set_style(pango::Style::Italic);
+        }
+        desc
+    }
+
+    pub fn font_options(&self) -> Option<cairo::FontOptions> {
+        let mut options = cairo::FontOptions::new().ok()?;
+        options.set_hint_metrics(cairo::HintMetrics::On);
+        if let Some(antialias) = self.antialias {
+            options.set_antialias(match antialias {
+                Antialias::None => cairo::Antialias::None,
+                Antialias::Gray => cairo::Antialias::Gray,
+                Antialias::Subpixel => cairo::Antialias::Subpixel,
+            });
+        }
+        if let Some(hinting) = self.hinting {
+            options.set_hint_style(match hinting {
+                Hinting::None => cairo::HintStyle::None,
+                Hinting::Slight => cairo::HintStyle::Slight,
+                Hinting::Medium => cairo::HintStyle::Medium,
+                Hinting::Full => cairo::HintStyle::Full,
+            });
+        }
+        Some(options)
+    }
+}
+
+fn weight_from_u16(weight: u16) -> pango::Weight {
+    match weight {
+        0..=149 => pango::Weight::Thin,
+        150..=249 => pango::Weight::Ultralight,
+        250..=324 => pango::Weight::Light,
+        325..=364 => pango::Weight::Semilight,
+        365..=389 => pango::Weight::Book,
+        390..=449 => pango::Weight::Normal,
+        450..=549 => pango::Weight::Medium,
+        550..=649 => pango::Weight::Semibold,
+        650..=749 => pango::Weight::Bold,
+        750..=849 => pango::Weight::Ultrabold,
+        850..=949 => pango::Weight::Heavy,
+        _ => pango::Weight::Ultraheavy,
+    }
+}
+
+/// Split by `sep`, a backslash escapes the separator.
+fn split_escaped(s: &str, sep: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&sep) => {
+                part.push(sep);
+                chars.next();
+            }
+            c if c == sep => {
+                parts.push(std::mem::take(&mut part));
+            }
+            c => part.push(c),
+        }
+    }
+    parts.push(part);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_font_settings_values() {
+        let value = nvim::Value::from("subpixelantialias");
+        let antialias = Antialias::parse_from_value(&value).unwrap();
+        assert_eq!(antialias, Antialias::Subpixel);
+        assert_eq!(antialias.to_value(), value);
+        assert!(Antialias::parse_from_value(&nvim::Value::from("gray")).is_err());
+        assert!(Antialias::parse_from_value(&nvim::Value::from(1)).is_err());
+
+        let value = nvim::Value::from("normal");
+        assert_eq!(Hinting::parse_from_value(&value), Ok(Hinting::Medium));
+        assert_eq!(Hinting::Medium.to_value(), nvim::Value::from("medium"));
+        assert_eq!(
+            Option::<Hinting>::parse_from_value(&nvim::Value::from("")),
+            Ok(None)
+        );
+        assert!(Hinting::parse_from_value(&nvim::Value::from("strong")).is_err());
+    }
+
+    #[test]
+    fn test_parse_single() {
+        let font = GuiFont::parse("Cascadia Code:h12").unwrap();
+        assert_eq!(font.families, vec!["Cascadia Code"]);
+        assert_eq!(font.size, Some(12.));
+        assert!(!font.bold);
+        assert!(!font.italic);
+    }
+
+    #[test]
+    fn test_parse_fallbacks() {
+        let font =
+            GuiFont::parse("Cascadia Code, Symbols Nerd Font Mono Light,Noto_Color_Emoji:h11.5")
+                .unwrap();
+        assert_eq!(
+            font.families,
+            vec![
+                "Cascadia Code",
+                "Symbols Nerd Font Mono Light",
+                "Noto Color Emoji"
+            ]
+        );
+        assert_eq!(font.size, Some(11.5));
+        assert_eq!(
+            font.family(),
+            "Cascadia Code,Symbols Nerd Font Mono Light,Noto Color Emoji"
+        );
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let font =
+            GuiFont::parse("Fira Code:h10:b:i:w-1.5:W300:#e-subpixelantialias:#h-full").unwrap();
+        assert_eq!(font.size, Some(10.));
+        assert!(font.bold);
+        assert!(font.italic);
+        assert_eq!(font.width, -1.5);
+        assert_eq!(font.weight, Some(300));
+        assert_eq!(font.antialias, Some(Antialias::Subpixel));
+        assert_eq!(font.hinting, Some(Hinting::Full));
+    }
+
+    #[test]
+    fn test_parse_options_per_entry() {
+        let font = GuiFont::parse("Iosevka:h13,Symbols Nerd Font:#e-alias").unwrap();
+        assert_eq!(font.families, vec!["Iosevka", "Symbols Nerd Font"]);
+        assert_eq!(font.size, Some(13.));
+        assert_eq!(font.antialias, Some(Antialias::None));
+    }
+
+    #[test]
+    fn test_parse_escaped() {
+        let font = GuiFont::parse(r"Foo\,Bar,Baz\:Qux:h9").unwrap();
+        assert_eq!(font.families, vec!["Foo,Bar", "Baz:Qux"]);
+        assert_eq!(font.size, Some(9.));
+    }
+
+    #[test]
+    fn test_parse_default_size() {
+        let font = GuiFont::parse("monospace").unwrap();
+        assert_eq!(font.size, None);
+        assert_eq!(font.size(), DEFAULT_FONT_SIZE);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(GuiFont::parse("  "), Err(GuiFontError::Empty));
+        assert_eq!(GuiFont::parse("Foo,,Bar"), Err(GuiFontError::EmptyFamily));
+        assert_eq!(
+            GuiFont::parse("Foo:hbig"),
+            Err(GuiFontError::InvalidSize("big".to_string()))
+        );
+        assert_eq!(
+            GuiFont::parse("Foo:h-1"),
+            Err(GuiFontError::InvalidSize("-1".to_string()))
+        );
+        assert_eq!(
+            GuiFont::parse("Foo:W50"),
+            Err(GuiFontError::InvalidWeight("50".to_string()))
+        );
+        assert_eq!(
+            GuiFont::parse("Foo:#e-fancy"),
+            Err(GuiFontError::InvalidAntialias("fancy".to_string()))
+        );
+        assert_eq!(
+            GuiFont::parse("Foo:#h-max"),
+            Err(GuiFontError::InvalidHinting("max".to_string()))
+        );
+        assert_eq!(
+            GuiFont::parse("Foo:x"),
+            Err(GuiFontError::UnknownOption("x".to_string()))
+        );
+        assert_eq!(
+            GuiFont::parse("Foo:bold"),
+            Err(GuiFontError::UnknownOption("bold".to_string()))
+        );
+    }
+}
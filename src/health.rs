@@ -0,0 +1,100 @@
+//! What `:checkhealth reovim` reports, see `runtime/lua/reovim/health.lua`. Filled in where it
+//! is found out and read by the `reovim.health` request.
+
+use std::collections::HashMap;
+
+use nvim::Value;
+
+#[derive(Debug, Default)]
+pub struct Health {
+    /// `child` or `tcp HOST:PORT`.
+    pub connection: String,
+    /// ui extensions nvim lists for this ui.
+    pub extensions: Vec<String>,
+    /// the channel of this ui, see `setup_neovide_specific_state`.
+    pub channel: Option<u64>,
+    /// families from `guifont` and the ones pango resolved them to, in fallback order.
+    pub fonts: Vec<(String, String)>,
+    /// same for `guifontwide`.
+    pub wide_fonts: Vec<(String, String)>,
+    pub clipboard: String,
+    /// variables set that have no equivalent, like `g:neovide_cursor_vfx_mode`.
+    pub unsupported: Vec<String>,
+    /// glyphs no font of the chain has, by grid, as last drawn.
+    unknown_glyphs: HashMap<u64, i32>,
+}
+
+impl Health {
+    pub fn set_unknown_glyphs(&mut self, grid: u64, count: i32) {
+        if count > 0 {
+            self.unknown_glyphs.insert(grid, count);
+        } else {
+            self.unknown_glyphs.remove(&grid);
+        }
+    }
+
+    pub fn forget_grid(&mut self, grid: u64) {
+        self.unknown_glyphs.remove(&grid);
+    }
+
+    pub fn unknown_glyphs_count(&self) -> i64 {
+        self.unknown_glyphs
+            .values()
+            .map(|count| *count as i64)
+            .sum()
+    }
+
+    /// `clipboard_provider` is what `provider#clipboard#Executable()` of nvim returns.
+    pub fn to_value(&self, clipboard_provider: &str) -> Value {
+        let fonts = |fonts: &[(String, String)]| {
+            Value::Array(
+                fonts
+                    .iter()
+                    .map(|(family, resolved)| {
+                        Value::Map(vec![
+                            ("family".into(), family.as_str().into()),
+                            ("resolved".into(), resolved.as_str().into()),
+                        ])
+                    })
+                    .collect(),
+            )
+        };
+        let strings =
+            |strings: Vec<String>| Value::Array(strings.into_iter().map(Value::from).collect());
+        Value::Map(vec![
+            ("version".into(), env!("CARGO_PKG_VERSION").into()),
+            ("connection".into(), self.connection.as_str().into()),
+            ("extensions".into(), strings(self.extensions.clone())),
+            (
+                "channel".into(),
+                self.channel.map(Value::from).unwrap_or(Value::Nil),
+            ),
+            ("fonts".into(), fonts(&self.fonts)),
+            ("wide_fonts".into(), fonts(&self.wide_fonts)),
+            ("unknown_glyphs".into(), self.unknown_glyphs_count().into()),
+            ("clipboard".into(), self.clipboard.as_str().into()),
+            ("clipboard_provider".into(), clipboard_provider.into()),
+            ("unsupported".into(), strings(self.unsupported.clone())),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_glyphs() {
+        let mut health = Health::default();
+        health.set_unknown_glyphs(1, 3);
+        health.set_unknown_glyphs(2, 4);
+        assert_eq!(health.unknown_glyphs_count(), 7);
+        // a grid counts as it was drawn last.
+        health.set_unknown_glyphs(1, 1);
+        assert_eq!(health.unknown_glyphs_count(), 5);
+        health.set_unknown_glyphs(1, 0);
+        assert_eq!(health.unknown_glyphs_count(), 4);
+        health.forget_grid(2);
+        assert_eq!(health.unknown_glyphs_count(), 0);
+    }
+}
@@ -0,0 +1,221 @@
+//! `--single-instance`, a second `rv` hands its files to the running one over D-Bus.
+
+use std::path::Path;
+
+use gtk::gio;
+use gtk::prelude::*;
+
+/// Name of the application on the session bus.
+pub const APP_ID: &str = "io.github.songww.reovim";
+
+/// Application action taking `(cwd, files)`, activated remotely by a second instance.
+/// Every window has one of the same name, the application one hands over to the active window.
+pub const OPEN_FILES_ACTION: &str = "open-files";
+
+/// The application relm4 runs, only unique on the session bus with `single_instance`.
+pub fn application(single_instance: bool) -> adw::Application {
+    let flags = if single_instance {
+        gio::ApplicationFlags::empty()
+    } else {
+        gio::ApplicationFlags::NON_UNIQUE
+    };
+    adw::Application::builder()
+        .application_id(APP_ID)
+        .flags(flags)
+        .build()
+}
+
+/// Hands `files` and the working directory to the running instance, if there is one.
+/// Returns false if this is the first instance, which goes on to open a window.
+pub fn forward(app: &impl IsA<gio::Application>, files: &[String]) -> bool {
+    if let Err(err) = app.register(gio::Cancellable::NONE) {
+        log::error!("could not register {}: {}", APP_ID, err);
+        return false;
+    }
+    if !app.is_remote() {
+        return false;
+    }
+    let cwd = std::env::current_dir()
+        .map(|cwd| cwd.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    log::info!("opening {:?} in the running instance", files);
+    app.activate_action(OPEN_FILES_ACTION, Some(&(cwd, files.to_vec()).to_variant()));
+    // the call is queued, send it before exiting.
+    if let Some(connection) = app.dbus_connection() {
+        if let Err(err) = connection.flush_sync(gio::Cancellable::NONE) {
+            log::error!("could not reach the running instance: {}", err);
+        }
+    }
+    true
+}
+
+/// Adds the application action that `forward` activates, the files open in the active window.
+pub fn add_open_files_action(app: &gtk::Application) {
+    let open_files = gio::SimpleAction::new(
+        OPEN_FILES_ACTION,
+        Some(gtk::glib::VariantTy::new("(sas)").unwrap()),
+    );
+    open_files.connect_activate(gtk::glib::clone!(@weak app => move |_, parameter| {
+        let window = match app.active_window() {
+            Some(window) => window,
+            None => {
+                log::warn!("no window to open {:?} in", parameter);
+                return;
+            }
+        };
+        let action = format!("win.{}", OPEN_FILES_ACTION);
+        if let Err(err) = window.activate_action(&action, parameter) {
+            log::error!("could not open files: {}", err);
+        }
+    }));
+    app.add_action(&open_files);
+}
+
+/// Files given relative to the working directory of the instance that forwarded them.
+pub fn absolute_paths(cwd: &str, files: &[String]) -> Vec<String> {
+    files
+        .iter()
+        .map(|file| Path::new(cwd).join(file).to_string_lossy().into_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
+
+    use gtk::glib;
+
+    use super::*;
+
+    /// Set in the child process `test_forward` starts as the second instance.
+    const FORWARD_ENV: &str = "REOVIM_TEST_FORWARD";
+
+    fn forwarded_files() -> Vec<String> {
+        vec![String::from("a.txt"), String::from("b c.txt")]
+    }
+
+    /// The second instance, run by `test_forward` in a child process, a no-op otherwise.
+    #[test]
+    fn test_forward_child() {
+        if std::env::var_os(FORWARD_ENV).is_none() {
+            return;
+        }
+        let app = gio::Application::new(Some(APP_ID), gio::ApplicationFlags::empty());
+        assert!(forward(&app, &forwarded_files()));
+    }
+
+    /// Kills the private `dbus-daemon` of `test_forward`, also when an assertion fails.
+    struct Daemon(Child);
+
+    impl Drop for Daemon {
+        fn drop(&mut self) {
+            self.0.kill().ok();
+            self.0.wait().ok();
+        }
+    }
+
+    #[test]
+    fn test_forward() {
+        // a private session bus, so no running rv gets the files. Only the child sees its
+        // address, the environment of the other tests is left alone.
+        let mut daemon = match Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+        {
+            Ok(daemon) => Daemon(daemon),
+            Err(err) => {
+                eprintln!("test_forward skipped, could not start dbus-daemon: {}", err);
+                return;
+            }
+        };
+        let mut address = String::new();
+        BufReader::new(daemon.0.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+        let address = address.trim();
+
+        let context = glib::MainContext::default();
+        let _guard = context.acquire().unwrap();
+        let connection = gio::DBusConnection::for_address_sync(
+            address,
+            gio::DBusConnectionFlags::AUTHENTICATION_CLIENT
+                | gio::DBusConnectionFlags::MESSAGE_BUS_CONNECTION,
+            None,
+            gio::Cancellable::NONE,
+        )
+        .unwrap();
+        // stands in for the first instance: its actions at the path of the application, then
+        // the name, which makes the child remote.
+        let received = Rc::new(RefCell::new(None));
+        let open_files = gio::SimpleAction::new(
+            OPEN_FILES_ACTION,
+            Some(glib::VariantTy::new("(sas)").unwrap()),
+        );
+        open_files.connect_activate(glib::clone!(@strong received => move |_, parameter| {
+            received.replace(parameter.and_then(|parameter| parameter.get::<(String, Vec<String>)>()));
+        }));
+        let actions = gio::SimpleActionGroup::new();
+        actions.add_action(&open_files);
+        let object_path = format!("/{}", APP_ID.replace('.', "/"));
+        connection
+            .export_action_group(&object_path, &actions)
+            .unwrap();
+        let reply = connection
+            .call_sync(
+                Some("org.freedesktop.DBus"),
+                "/org/freedesktop/DBus",
+                "org.freedesktop.DBus",
+                "RequestName",
+                Some(&(APP_ID, 0u32).to_variant()),
+                Some(glib::VariantTy::new("(u)").unwrap()),
+                gio::DBusCallFlags::NONE,
+                -1,
+                gio::Cancellable::NONE,
+            )
+            .unwrap();
+        // DBUS_REQUEST_NAME_REPLY_PRIMARY_OWNER
+        assert_eq!(reply.get::<(u32,)>(), Some((1,)));
+
+        let mut child = Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "instance::tests::test_forward_child"])
+            .env(FORWARD_ENV, "1")
+            .env("DBUS_SESSION_BUS_ADDRESS", address)
+            .spawn()
+            .unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while received.borrow().is_none() && Instant::now() < deadline {
+            if !context.iteration(false) {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+        assert!(child.wait().unwrap().success());
+
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            received.take(),
+            Some((cwd.to_string_lossy().into_owned(), forwarded_files()))
+        );
+    }
+
+    #[test]
+    fn test_absolute_paths() {
+        let files = vec![
+            String::from("a.txt"),
+            String::from("src/b.rs"),
+            String::from("/etc/hosts"),
+        ];
+        assert_eq!(
+            absolute_paths("/home/user/project", &files),
+            vec![
+                "/home/user/project/a.txt",
+                "/home/user/project/src/b.rs",
+                "/etc/hosts",
+            ]
+        );
+    }
+}
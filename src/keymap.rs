@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use nvim::Value;
+
+/// What a key chord of `g:reovim_keymap` does instead of being sent to neovim.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Copy the gui selection, or the visual selection, to the clipboard.
+    Copy,
+    /// Paste the clipboard.
+    Paste,
+    /// Paste the primary selection.
+    PastePrimary,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    ToggleFullscreen,
+    NewWindow,
+    /// Send these keys, in `<>` notation, to neovim.
+    Keys(String),
+}
+
+impl From<&str> for Action {
+    fn from(action: &str) -> Self {
+        match action {
+            "copy" => Action::Copy,
+            "paste" => Action::Paste,
+            "paste_primary" => Action::PastePrimary,
+            "zoom_in" => Action::ZoomIn,
+            "zoom_out" => Action::ZoomOut,
+            "zoom_reset" => Action::ZoomReset,
+            "fullscreen" => Action::ToggleFullscreen,
+            "new_window" => Action::NewWindow,
+            keys => Action::Keys(keys.to_string()),
+        }
+    }
+}
+
+const SHIFT: u8 = 1;
+const CTRL: u8 = 1 << 1;
+const ALT: u8 = 1 << 2;
+const SUPER: u8 = 1 << 3;
+
+/// A key chord in `<>` notation, normalized so that `<C-S-v>`, `<s-c-V>` and the translated
+/// key press `<S-C-V>` compare equal.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Chord {
+    modifiers: u8,
+    key: String,
+}
+
+impl Chord {
+    pub fn parse(chord: &str) -> Option<Chord> {
+        let mut modifiers = 0;
+        let mut key = match chord.strip_prefix('<').and_then(|c| c.strip_suffix('>')) {
+            Some(inner) if !inner.is_empty() => inner,
+            _ => chord,
+        };
+        if key.len() != chord.len() {
+            // `<C-->` is Ctrl and minus.
+            while key.len() > 2 && key.as_bytes()[1] == b'-' {
+                modifiers |= match key.as_bytes()[0].to_ascii_uppercase() {
+                    b'S' => SHIFT,
+                    b'C' => CTRL,
+                    b'A' | b'M' => ALT,
+                    b'D' => SUPER,
+                    _ => return None,
+                };
+                key = &key[2..];
+            }
+        }
+
+        let mut chars = key.chars();
+        let key = match (chars.next()?, chars.next()) {
+            (c, None) => {
+                // a lone capital letter is typed with shift.
+                if c.is_uppercase() && (modifiers == 0 || modifiers & SHIFT != 0) {
+                    modifiers |= SHIFT;
+                }
+                match c {
+                    '<' => "lt".to_string(),
+                    '\\' => "bslash".to_string(),
+                    '|' => "bar".to_string(),
+                    ' ' => "space".to_string(),
+                    c => c.to_lowercase().collect(),
+                }
+            }
+            _ if key.len() == chord.len() => return None,
+            _ => match key.to_lowercase().as_str() {
+                "return" | "enter" => "cr".to_string(),
+                "escape" => "esc".to_string(),
+                "delete" => "del".to_string(),
+                "backspace" => "bs".to_string(),
+                key => key.to_string(),
+            },
+        };
+        Some(Chord { modifiers, key })
+    }
+}
+
+/// Bindings `g:reovim_keymap` adds to, or overrides.
+const DEFAULT_BINDINGS: &[(&str, &str)] = &[
+    ("<C-S-c>", "copy"),
+    ("<C-S-v>", "paste"),
+    ("<MiddleMouse>", "paste_primary"),
+    ("<C-S-n>", "new_window"),
+    ("<C-=>", "zoom_in"),
+    ("<C-+>", "zoom_in"),
+    ("<C-kPlus>", "zoom_in"),
+    ("<C-->", "zoom_out"),
+    ("<C-_>", "zoom_out"),
+    ("<C-kMinus>", "zoom_out"),
+    ("<C-0>", "zoom_reset"),
+    ("<C-k0>", "zoom_reset"),
+    ("<F11>", "fullscreen"),
+];
+
+/// Gui key bindings from `g:reovim_keymap`, checked before a key press is sent to neovim:
+///
+/// ```vim
+/// let g:reovim_keymap = {'<D-c>': 'copy', '<D-v>': 'paste', '<A-x>': '<Esc>:'}
+/// ```
+#[derive(Clone, Debug)]
+pub struct Keymap(HashMap<Chord, Action>);
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap(
+            DEFAULT_BINDINGS
+                .iter()
+                .map(|(chord, action)| (Chord::parse(chord).unwrap(), Action::from(*action)))
+                .collect(),
+        )
+    }
+}
+
+impl Keymap {
+    pub fn get(&self, input: &str) -> Option<&Action> {
+        self.0.get(&Chord::parse(input)?)
+    }
+
+    /// Adds the bindings of a dictionary, replacing those of the same chords.
+    /// Nothing is added if any binding is invalid.
+    pub fn extend(&mut self, value: &Value) -> Result<(), String> {
+        let map = value
+            .as_map()
+            .ok_or_else(|| format!("expected a dictionary, got {}", value))?;
+        let mut bindings = Vec::with_capacity(map.len());
+        for (chord, action) in map {
+            let (chord, action) = match (chord.as_str(), action.as_str()) {
+                (Some(chord), Some(action)) => (chord, action),
+                _ => return Err(format!("invalid binding {}: {}", chord, action)),
+            };
+            let parsed = Chord::parse(chord).ok_or_else(|| format!("invalid key {}", chord))?;
+            bindings.push((parsed, Action::from(action)));
+        }
+        self.0.extend(bindings);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chord_normalized() {
+        let cases = [
+            ("<C-S-v>", "<S-C-V>"),
+            ("<s-c-V>", "<S-C-V>"),
+            ("<M-x>", "<A-x>"),
+            ("<C-v>", "<C-V>"),
+            ("<C-Return>", "<C-CR>"),
+            ("<c-cr>", "<C-CR>"),
+            ("<C-lt>", "<C-<>"),
+            ("<C-Bslash>", "<C-\\>"),
+            ("A", "<S-A>"),
+            ("<lt>", "<"),
+        ];
+        for (a, b) in cases {
+            assert_eq!(Chord::parse(a), Chord::parse(b), "{} == {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_chord_distinct() {
+        let cases = [
+            ("<C-v>", "<S-C-V>"),
+            ("a", "A"),
+            ("<A-x>", "<D-x>"),
+            ("<C-->", "<C-=>"),
+            ("<F1>", "<S-F1>"),
+        ];
+        for (a, b) in cases {
+            assert_ne!(Chord::parse(a), Chord::parse(b), "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_chord_invalid() {
+        assert_eq!(Chord::parse(""), None);
+        assert_eq!(Chord::parse("ab"), None);
+        assert_eq!(Chord::parse("<X-a>"), None);
+        assert!(Chord::parse("<").is_some());
+        assert!(Chord::parse("<>").is_none());
+    }
+
+    #[test]
+    fn test_keymap_bindings() {
+        let value = Value::Map(vec![
+            ("<D-c>".into(), "copy".into()),
+            ("<C-S-v>".into(), "paste".into()),
+            ("<A-x>".into(), "<Esc>:".into()),
+        ]);
+        let mut keymap = Keymap::default();
+        keymap.extend(&value).unwrap();
+        assert_eq!(keymap.get("<D-c>"), Some(&Action::Copy));
+        assert_eq!(keymap.get("<S-C-V>"), Some(&Action::Paste));
+        assert_eq!(
+            keymap.get("<M-x>"),
+            Some(&Action::Keys("<Esc>:".to_string()))
+        );
+        assert_eq!(keymap.get("<C-v>"), None);
+
+        // defaults can be overridden.
+        assert_eq!(Keymap::default().get("<S-C-V>"), Some(&Action::Paste));
+        assert_eq!(Keymap::default().get("<S-C-C>"), Some(&Action::Copy));
+        assert_eq!(
+            Keymap::default().get("<F11>"),
+            Some(&Action::ToggleFullscreen)
+        );
+        assert_eq!(
+            Keymap::default().get("<MiddleMouse>"),
+            Some(&Action::PastePrimary)
+        );
+        assert_eq!(Keymap::default().get("<C-->"), Some(&Action::ZoomOut));
+        assert_eq!(Keymap::default().get("<C-+>"), Some(&Action::ZoomIn));
+        assert_eq!(Keymap::default().get("<C-k0>"), Some(&Action::ZoomReset));
+        let value = Value::Map(vec![("<C-S-V>".into(), "<C-S-V>".into())]);
+        let mut keymap = Keymap::default();
+        keymap.extend(&value).unwrap();
+        assert_eq!(
+            keymap.get("<S-C-V>"),
+            Some(&Action::Keys("<C-S-V>".to_string()))
+        );
+
+        assert!(Keymap::default().extend(&Value::from(1)).is_err());
+        let invalid = Value::Map(vec![("<D-c>".into(), 1.into())]);
+        assert!(Keymap::default().extend(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_keymap_extend() {
+        let mut keymap = Keymap::default();
+        let config = Value::Map(vec![("<D-c>".into(), "copy".into())]);
+        keymap.extend(&config).unwrap();
+        let user = Value::Map(vec![("<D-v>".into(), "paste".into())]);
+        keymap.extend(&user).unwrap();
+        assert_eq!(keymap.get("<D-c>"), Some(&Action::Copy));
+        assert_eq!(keymap.get("<D-v>"), Some(&Action::Paste));
+
+        // an invalid binding leaves the others out too.
+        let invalid = Value::Map(vec![
+            ("<D-x>".into(), "copy".into()),
+            ("<X-y>".into(), "paste".into()),
+        ]);
+        assert!(keymap.extend(&invalid).is_err());
+        assert_eq!(keymap.get("<D-x>"), None);
+    }
+}
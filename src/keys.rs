@@ -1,66 +1,172 @@
+use std::borrow::Cow;
+
+use glib::translate::IntoGlib;
 use gtk::gdk;
+use gtk::gdk::Key;
+
+use crate::keymap::Keymap;
+use crate::settings::setting_group;
 
 pub trait ToInput {
     fn to_input(&self) -> Option<String>;
 }
 
-fn map_keyname(keyname: String) -> Option<&'static str> {
-    // Originally sourced from python-gui.
-    match keyname.as_ref() {
-        "asciicircum" => "^".into(), // fix #137
-        "slash" => "/".into(),
-        "backslash" => "\\".into(),
-        "dead_circumflex" => "^".into(),
-        "at" => "@".into(),
-        "numbersign" => "#".into(),
-        "colon" => ":".into(),
-        "dollar" => "$".into(),
-        "percent" => "%".into(),
-        "ampersand" => "&".into(),
-        "asterisk" => "*".into(),
-        "parenleft" => "(".into(),
-        "parenright" => ")".into(),
-        "underscore" => "_".into(),
-        "plus" => "+".into(),
-        "minus" => "-".into(),
-        "bracketleft" => "[".into(),
-        "bracketright" => "]".into(),
-        "braceleft" => "{".into(),
-        "braceright" => "}".into(),
-        "dead_diaeresis" => "\"".into(),
-        "dead_acute" => "\'".into(),
-        "less" => "<".into(),
-        "greater" => ">".into(),
-        "comma" => ",".into(),
-        "period" => ".".into(),
-        "BackSpace" => "BS".into(),
-        "Insert" => "Insert".into(),
-        "Return" => "CR".into(),
-        "Escape" => "Esc".into(),
-        "Delete" => "Del".into(),
-        "Page_Up" => "PageUp".into(),
-        "Page_Down" => "PageDown".into(),
-        "Enter" => "CR".into(),
-        "ISO_Left_Tab" => "Tab".into(),
-        "Tab" => "Tab".into(),
-        "Up" => "Up".into(),
-        "Down" => "Down".into(),
-        "Left" => "Left".into(),
-        "Right" => "Right".into(),
-        "Home" => "Home".into(),
-        "End" => "End".into(),
-        "F1" => "F1".into(),
-        "F2" => "F2".into(),
-        "F3" => "F3".into(),
-        "F4" => "F4".into(),
-        "F5" => "F5".into(),
-        "F6" => "F6".into(),
-        "F7" => "F7".into(),
-        "F8" => "F8".into(),
-        "F9" => "F9".into(),
-        "F10" => "F10".into(),
-        "F11" => "F11".into(),
-        "F12" => "F12".into(),
+#[derive(Clone, Debug)]
+pub struct KeyboardSettings {
+    /// Translate shortcuts to the latin layout, `g:reovim_latin_shortcuts`.
+    pub latin_shortcuts: bool,
+    /// Alt sends `<M-…>` instead of `<A-…>`, `g:reovim_alt_as_meta`.
+    pub alt_as_meta: bool,
+    /// Super sends `<M-…>` instead of `<D-…>`, `g:reovim_super_as_meta`.
+    pub super_as_meta: bool,
+    /// `g:reovim_keymap`.
+    pub keymap: Keymap,
+}
+
+impl Default for KeyboardSettings {
+    fn default() -> Self {
+        KeyboardSettings {
+            latin_shortcuts: true,
+            alt_as_meta: false,
+            super_as_meta: false,
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+// `keymap` is bound on top of the config file, see `AppModel::build_keymap`.
+setting_group! {
+    KeyboardSettings {
+        "latin_shortcuts" => latin_shortcuts,
+        "alt_as_meta" => alt_as_meta,
+        "super_as_meta" => super_as_meta,
+    }
+}
+
+impl KeyboardSettings {
+    /// Modifiers of a key press with Alt or Super turned into Meta as configured. Meta reported
+    /// by the windowing system is dropped, it usually comes along with Alt.
+    pub fn modifiers(&self, modifier: gdk::ModifierType) -> gdk::ModifierType {
+        let mut modifier = modifier - gdk::ModifierType::META_MASK;
+        if self.alt_as_meta && modifier.contains(gdk::ModifierType::ALT_MASK) {
+            modifier = modifier - gdk::ModifierType::ALT_MASK | gdk::ModifierType::META_MASK;
+        }
+        if self.super_as_meta && modifier.contains(gdk::ModifierType::SUPER_MASK) {
+            modifier = modifier - gdk::ModifierType::SUPER_MASK | gdk::ModifierType::META_MASK;
+        }
+        modifier
+    }
+}
+
+/// On non-latin layouts `Ctrl+w` produces `<C-ц>`, look the key up in the first layout group
+/// that produces latin text instead, like terminals do.
+///
+/// Keys which already are ascii, or which are pressed without Ctrl, Alt or Super, are kept.
+pub fn latin_keyval(
+    display: &gdk::Display,
+    keyval: Key,
+    keycode: u32,
+    modifier: gdk::ModifierType,
+) -> Key {
+    let shortcut = gdk::ModifierType::CONTROL_MASK
+        | gdk::ModifierType::ALT_MASK
+        | gdk::ModifierType::SUPER_MASK;
+    if !modifier.intersects(shortcut) || keyval.to_unicode().map_or(true, |c| c.is_ascii()) {
+        return keyval;
+    }
+    let (keys, _) = match display.map_keycode(keycode) {
+        Some(keys) => keys,
+        None => return keyval,
+    };
+    let mut groups: Vec<i32> = keys.iter().map(|key| key.group()).collect();
+    groups.sort_unstable();
+    groups.dedup();
+    groups
+        .into_iter()
+        .filter_map(|group| {
+            display.translate_key(keycode, modifier & gdk::ModifierType::SHIFT_MASK, group)
+        })
+        .map(|(keyval, ..)| keyval)
+        .find(|keyval| keyval.to_unicode().map_or(false, |c| c.is_ascii_graphic()))
+        .unwrap_or(keyval)
+}
+
+/// Modifiers neovim knows about, others like caps lock are ignored.
+fn modifiers(modifier: gdk::ModifierType) -> gdk::ModifierType {
+    modifier
+        & (gdk::ModifierType::SHIFT_MASK
+            | gdk::ModifierType::CONTROL_MASK
+            | gdk::ModifierType::ALT_MASK
+            | gdk::ModifierType::SUPER_MASK
+            | gdk::ModifierType::META_MASK)
+}
+
+/// Name in neovim's `<>` notation of keys which are not plain text.
+fn special_key(key: Key) -> Option<Cow<'static, str>> {
+    let name = match key {
+        Key::BackSpace => "BS",
+        Key::Tab | Key::ISO_Left_Tab | Key::KP_Tab => "Tab",
+        Key::Return | Key::ISO_Enter | Key::_3270_Enter => "CR",
+        Key::Escape => "Esc",
+        Key::Delete => "Del",
+        Key::Insert => "Insert",
+        Key::Up => "Up",
+        Key::Down => "Down",
+        Key::Left => "Left",
+        Key::Right => "Right",
+        Key::Home => "Home",
+        Key::End => "End",
+        Key::Page_Up => "PageUp",
+        Key::Page_Down => "PageDown",
+        Key::Help => "Help",
+        Key::Undo => "Undo",
+        Key::Menu => "Menu",
+        // keypad
+        Key::KP_Enter => "kEnter",
+        Key::KP_Up => "kUp",
+        Key::KP_Down => "kDown",
+        Key::KP_Left => "kLeft",
+        Key::KP_Right => "kRight",
+        Key::KP_Home => "kHome",
+        Key::KP_End => "kEnd",
+        Key::KP_Begin => "kOrigin",
+        Key::KP_Page_Up => "kPageUp",
+        Key::KP_Page_Down => "kPageDown",
+        Key::KP_Insert => "kInsert",
+        Key::KP_Delete => "kDel",
+        Key::KP_Add => "kPlus",
+        Key::KP_Subtract => "kMinus",
+        Key::KP_Multiply => "kMultiply",
+        Key::KP_Divide => "kDivide",
+        Key::KP_Decimal => "kPoint",
+        Key::KP_Separator => "kComma",
+        Key::KP_Equal => "kEqual",
+        Key::KP_F1 => "F1",
+        Key::KP_F2 => "F2",
+        Key::KP_F3 => "F3",
+        Key::KP_F4 => "F4",
+        _ => {
+            let keyval = key.into_glib();
+            return if (Key::KP_0.into_glib()..=Key::KP_9.into_glib()).contains(&keyval) {
+                Some(format!("k{}", keyval - Key::KP_0.into_glib()).into())
+            } else if (Key::F1.into_glib()..=Key::F35.into_glib()).contains(&keyval) {
+                Some(format!("F{}", keyval - Key::F1.into_glib() + 1).into())
+            } else {
+                None
+            };
+        }
+    };
+    Some(name.into())
+}
+
+/// Dead keys that reach us were not composed by the input method, send their symbol.
+fn dead_key(key: Key) -> Option<char> {
+    match key {
+        Key::dead_grave => '`'.into(),
+        Key::dead_acute => '\''.into(),
+        Key::dead_circumflex => '^'.into(),
+        Key::dead_tilde => '~'.into(),
+        Key::dead_diaeresis => '"'.into(),
         _ => None,
     }
 }
@@ -79,27 +185,192 @@ impl ToInput for gdk::ModifierType {
             input.push_str("A-");
         }
         if self.contains(gdk::ModifierType::SUPER_MASK) {
+            input.push_str("D-");
+        }
+        if self.contains(gdk::ModifierType::META_MASK) {
             input.push_str("M-");
         }
 
-        Some(format!("{}", input).into())
+        Some(input)
     }
 }
 
 impl ToInput for (gdk::Key, gdk::ModifierType) {
     fn to_input(&self) -> Option<String> {
-        let modkey = self.1.to_input()?;
-        let keyname = self.0.name().unwrap();
-
-        if keyname.chars().count() > 1 {
-            format!("<{}{}>", modkey, map_keyname(keyname.to_string())?).into()
-        } else {
-            let k = self.0.to_unicode().unwrap();
-            if !self.1.is_empty() {
-                format!("<{}{}>", modkey, k).into()
+        let (key, modifier) = *self;
+        let mut modifier = modifiers(modifier);
+
+        if matches!(key, Key::space | Key::KP_Space) {
+            return if modifier.is_empty() {
+                Some(" ".to_string())
             } else {
-                k.to_string().into()
-            }
+                Some(format!("<{}Space>", modifier.to_input()?))
+            };
         }
+
+        if let Some(name) = special_key(key) {
+            return Some(format!("<{}{}>", modifier.to_input()?, name));
+        }
+
+        let c = dead_key(key)
+            .or_else(|| key.to_unicode())
+            .filter(|c| !c.is_control())?;
+        // the keyval is shifted already, neovim does not understand `<S-:>`. Letters keep it
+        // together with other modifiers, so that `<S-C-A>` can be mapped.
+        let others = modifier - gdk::ModifierType::SHIFT_MASK;
+        if !(c.is_alphabetic() && !others.is_empty()) {
+            modifier = others;
+        }
+
+        if modifier.is_empty() {
+            return Some(match c {
+                '<' => "<lt>".to_string(),
+                c => c.to_string(),
+            });
+        }
+        let name: Cow<str> = match c {
+            '<' => "lt".into(),
+            '\\' => "Bslash".into(),
+            '|' => "Bar".into(),
+            c => c.to_string().into(),
+        };
+        Some(format!("<{}{}>", modifier.to_input()?, name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use gdk::ModifierType;
+
+    const NONE: ModifierType = ModifierType::empty();
+    const SHIFT: ModifierType = ModifierType::SHIFT_MASK;
+    const CTRL: ModifierType = ModifierType::CONTROL_MASK;
+    const ALT: ModifierType = ModifierType::ALT_MASK;
+    const SUPER: ModifierType = ModifierType::SUPER_MASK;
+    const LOCK: ModifierType = ModifierType::LOCK_MASK;
+    const META: ModifierType = ModifierType::META_MASK;
+
+    fn check(cases: &[(Key, ModifierType, Option<&str>)]) {
+        for (key, modifier, expected) in cases {
+            assert_eq!(
+                (*key, *modifier).to_input().as_deref(),
+                *expected,
+                "{:?} with {:?}",
+                key.name(),
+                modifier
+            );
+        }
+    }
+
+    #[test]
+    fn test_printable() {
+        check(&[
+            (Key::a, NONE, Some("a")),
+            (Key::A, SHIFT, Some("A")),
+            (Key::a, LOCK, Some("a")),
+            (Key::_1, NONE, Some("1")),
+            (Key::colon, SHIFT, Some(":")),
+            (Key::asciicircum, SHIFT, Some("^")),
+            (Key::bar, SHIFT, Some("|")),
+            (Key::backslash, NONE, Some("\\")),
+            (Key::less, SHIFT, Some("<lt>")),
+            (Key::eacute, NONE, Some("é")),
+            (Key::dead_circumflex, NONE, Some("^")),
+            (Key::dead_grave, SHIFT, Some("`")),
+        ]);
+    }
+
+    #[test]
+    fn test_printable_with_modifiers() {
+        check(&[
+            (Key::a, CTRL, Some("<C-a>")),
+            (Key::A, CTRL | SHIFT, Some("<S-C-A>")),
+            (Key::x, ALT, Some("<A-x>")),
+            (Key::s, SUPER, Some("<D-s>")),
+            (Key::bracketright, CTRL, Some("<C-]>")),
+            (Key::question, CTRL | SHIFT, Some("<C-?>")),
+            (Key::less, CTRL | SHIFT, Some("<C-lt>")),
+            (Key::backslash, CTRL, Some("<C-Bslash>")),
+            (Key::bar, ALT | SHIFT, Some("<A-Bar>")),
+        ]);
+    }
+
+    #[test]
+    fn test_space() {
+        check(&[
+            (Key::space, NONE, Some(" ")),
+            (Key::space, SHIFT, Some("<S-Space>")),
+            (Key::space, CTRL, Some("<C-Space>")),
+            (Key::KP_Space, NONE, Some(" ")),
+        ]);
+    }
+
+    #[test]
+    fn test_special() {
+        check(&[
+            (Key::Return, NONE, Some("<CR>")),
+            (Key::Escape, NONE, Some("<Esc>")),
+            (Key::BackSpace, CTRL, Some("<C-BS>")),
+            (Key::Tab, NONE, Some("<Tab>")),
+            (Key::ISO_Left_Tab, SHIFT, Some("<S-Tab>")),
+            (Key::Up, SHIFT, Some("<S-Up>")),
+            (Key::Page_Down, CTRL, Some("<C-PageDown>")),
+            (Key::Insert, SHIFT, Some("<S-Insert>")),
+            (Key::Insert, CTRL | SHIFT, Some("<S-C-Insert>")),
+            (Key::Help, NONE, Some("<Help>")),
+            (Key::Undo, NONE, Some("<Undo>")),
+            (Key::Menu, NONE, Some("<Menu>")),
+            (Key::F1, NONE, Some("<F1>")),
+            (Key::F12, SHIFT, Some("<S-F12>")),
+            (Key::F13, NONE, Some("<F13>")),
+            (Key::F35, CTRL, Some("<C-F35>")),
+        ]);
+    }
+
+    #[test]
+    fn test_keypad() {
+        check(&[
+            (Key::KP_0, NONE, Some("<k0>")),
+            (Key::KP_9, CTRL, Some("<C-k9>")),
+            (Key::KP_Enter, NONE, Some("<kEnter>")),
+            (Key::KP_Add, NONE, Some("<kPlus>")),
+            (Key::KP_Subtract, NONE, Some("<kMinus>")),
+            (Key::KP_Multiply, NONE, Some("<kMultiply>")),
+            (Key::KP_Divide, NONE, Some("<kDivide>")),
+            (Key::KP_Decimal, NONE, Some("<kPoint>")),
+            (Key::KP_Home, NONE, Some("<kHome>")),
+            (Key::KP_Begin, NONE, Some("<kOrigin>")),
+            (Key::KP_Delete, SHIFT, Some("<S-kDel>")),
+        ]);
+    }
+
+    #[test]
+    fn test_meta() {
+        check(&[
+            (Key::x, META, Some("<M-x>")),
+            (Key::Left, CTRL | META, Some("<C-M-Left>")),
+        ]);
+
+        let mut settings = KeyboardSettings::default();
+        assert_eq!(settings.modifiers(ALT | META), ALT);
+        assert_eq!(settings.modifiers(SUPER | CTRL), SUPER | CTRL);
+        settings.alt_as_meta = true;
+        assert_eq!(settings.modifiers(ALT | SHIFT), META | SHIFT);
+        assert_eq!(settings.modifiers(SUPER), SUPER);
+        settings.super_as_meta = true;
+        assert_eq!(settings.modifiers(SUPER | CTRL), META | CTRL);
+    }
+
+    #[test]
+    fn test_ignored() {
+        check(&[
+            (Key::Shift_L, SHIFT, None),
+            (Key::Control_R, CTRL, None),
+            (Key::Alt_L, NONE, None),
+            (Key::Caps_Lock, NONE, None),
+            (Key::VoidSymbol, NONE, None),
+        ]);
     }
 }
@@ -4,21 +4,31 @@ extern crate derive_new;
 extern crate derivative;
 
 use clap::{IntoApp, Parser};
+use gtk::prelude::*;
 
 mod app;
 mod bridge;
 mod color;
 mod components;
+mod config;
 mod cursor;
 mod event_aggregator;
 mod factory;
 mod grapheme;
+mod guifont;
+mod health;
+mod instance;
+mod keymap;
 mod keys;
 mod loggingchan;
 mod messager;
 mod metrics;
+mod pointer;
 mod running_tracker;
+mod scroll;
+mod session;
 mod settings;
+mod state;
 mod style;
 mod vimview;
 
@@ -37,12 +47,56 @@ pub struct Opts {
     #[clap(long = "remote", env = "REMOTE", value_name = "HOST:PORT")]
     remote_tcp: Option<String>,
 
-    // initial window width
-    #[clap(long = "window-width", env = "WIDTH", default_value_t = 800)]
-    width: i32,
-    // initial window height
-    #[clap(long = "window-height", env = "HEIGHT", default_value_t = 600)]
-    height: i32,
+    // initial window width, the saved one or 800 if not given
+    #[clap(long = "window-width", env = "WIDTH")]
+    width: Option<i32>,
+    // initial window height, the saved one or 600 if not given
+    #[clap(long = "window-height", env = "HEIGHT")]
+    height: Option<i32>,
+
+    /// Initial window size in cells, instead of --window-width and --window-height
+    #[clap(
+        long = "geometry",
+        value_name = "COLSxROWS",
+        conflicts_with_all = &["width", "height"]
+    )]
+    geometry: Option<Geometry>,
+
+    /// Start in fullscreen
+    #[clap(long = "fullscreen", overrides_with = "no-fullscreen")]
+    fullscreen: bool,
+
+    /// Do not start in fullscreen, even if config.toml or the saved state say so
+    #[clap(long = "no-fullscreen")]
+    no_fullscreen: bool,
+
+    /// Start maximized
+    #[clap(long = "maximized", overrides_with = "no-maximized")]
+    maximized: bool,
+
+    /// Do not start maximized, even if config.toml or the saved state say so
+    #[clap(long = "no-maximized")]
+    no_maximized: bool,
+
+    /// Hide the title bar and borders of the window
+    #[clap(long = "no-decorations", overrides_with = "decorations")]
+    no_decorations: bool,
+
+    /// Show the title bar and borders, even if config.toml turns them off
+    #[clap(long = "decorations")]
+    decorations: bool,
+
+    /// Neither restore nor save the window size and state
+    #[clap(long = "no-restore")]
+    no_restore: bool,
+
+    /// Open the files in a new tab of the running instance, if there is one
+    #[clap(long = "single-instance")]
+    single_instance: bool,
+
+    /// Start a separate instance, even with --single-instance
+    #[clap(long = "new-window")]
+    new_window: bool,
 
     /// A level of log, see: https://docs.rs/env_logger/latest/env_logger/#enabling-logging
     #[clap(short, long, value_name = "RUST_LOG", parse(from_occurrences))]
@@ -61,6 +115,31 @@ pub struct Opts {
 
     #[clap(skip)]
     size: Option<(i64, i64)>,
+
+    #[clap(skip)]
+    config: config::Config,
+}
+
+/// Window size in cells, `--geometry 120x40`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Geometry {
+    cols: i64,
+    rows: i64,
+}
+
+impl std::str::FromStr for Geometry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected COLSxROWS, like 120x40, got '{}'", s);
+        let (cols, rows) = s.split_once(['x', 'X']).ok_or_else(invalid)?;
+        let cols = cols.parse::<i64>().map_err(|_| invalid())?;
+        let rows = rows.parse::<i64>().map_err(|_| invalid())?;
+        if cols <= 0 || rows <= 0 {
+            return Err(invalid());
+        }
+        Ok(Geometry { cols, rows })
+    }
 }
 
 impl Opts {
@@ -71,6 +150,31 @@ impl Opts {
             ConnectionMode::Child
         }
     }
+
+    /// Fills in what neither the command line nor the environment gave from the config file.
+    /// The window size is left out, so it does not hide the saved one.
+    fn merge_config(&mut self, config: config::Config) {
+        if self.nvim_path.is_none() {
+            self.nvim_path = config.connection.nvim.clone();
+        }
+        if self.remote_tcp.is_none() {
+            self.remote_tcp = config.connection.remote.clone();
+        }
+        if self.nvim_args.is_empty() {
+            self.nvim_args = config.connection.args.clone();
+        }
+        // the flags given on the command line win, either way.
+        if !self.no_fullscreen {
+            self.fullscreen |= config.window.fullscreen;
+        }
+        if !self.no_maximized {
+            self.maximized |= config.window.maximized;
+        }
+        if !self.decorations {
+            self.no_decorations |= !config.window.decorations;
+        }
+        self.config = config;
+    }
 }
 
 fn main() {
@@ -85,12 +189,62 @@ fn main() {
     let env = env_logger::Env::default().default_filter_or(level);
     env_logger::Builder::from_env(env).init();
     log::trace!("command line options: {:?}", opts);
+    match config::Config::load() {
+        Ok(config) => opts.merge_config(config),
+        Err(err) => log::error!("failed to load config: {}", err),
+    }
     let app = Opts::command().allow_missing_positional(true);
     let title = app.get_bin_name().unwrap_or("rv");
     opts.title = title.to_string();
     log::trace!("opts: {:?}", opts);
-    let model = app::AppModel::new(opts);
-    let relm = relm4::RelmApp::new(model);
+    let single_instance = opts.single_instance && !opts.new_window;
+    let application = instance::application(single_instance);
+    if single_instance && instance::forward(&application, &opts.files) {
+        return;
+    }
+    session::run(application.upcast_ref(), opts, &[title]);
+}
 
-    relm.run_with_args(&[title]);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geometry_from_str() {
+        assert_eq!("120x40".parse(), Ok(Geometry { cols: 120, rows: 40 }));
+        assert_eq!("120X40".parse(), Ok(Geometry { cols: 120, rows: 40 }));
+        assert!("0x10".parse::<Geometry>().is_err());
+        assert!("x40".parse::<Geometry>().is_err());
+        assert!("abc".parse::<Geometry>().is_err());
+    }
+
+    #[test]
+    fn test_command_line_over_config() {
+        let mut config = config::Config::default();
+        config.window.fullscreen = true;
+        config.window.maximized = true;
+        config.window.decorations = false;
+
+        let mut opts = Opts::try_parse_from(&["rv"]).unwrap();
+        opts.merge_config(config.clone());
+        assert!(opts.fullscreen && opts.maximized && opts.no_decorations);
+
+        let mut opts =
+            Opts::try_parse_from(&["rv", "--no-fullscreen", "--no-maximized", "--decorations"])
+                .unwrap();
+        opts.merge_config(config);
+        assert!(!opts.fullscreen && !opts.maximized && !opts.no_decorations);
+
+        // the last one wins.
+        let opts = Opts::try_parse_from(&["rv", "--no-fullscreen", "--fullscreen"]).unwrap();
+        assert!(opts.fullscreen && !opts.no_fullscreen);
+    }
+
+    #[test]
+    fn test_geometry_conflicts_with_size() {
+        let opts = Opts::try_parse_from(&["rv", "--geometry", "120x40", "--window-width", "800"]);
+        assert!(opts.is_err());
+        let opts = Opts::try_parse_from(&["rv", "--geometry", "120x40"]).unwrap();
+        assert_eq!(opts.geometry, Some(Geometry { cols: 120, rows: 40 }));
+    }
 }
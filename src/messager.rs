@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use relm4::{MessageHandler, Sender};
 // use tokio::runtime::{Builder, Runtime};
 // use tokio::sync::mpsc::unbounded_channel as unbound;
@@ -5,22 +7,27 @@ use relm4::{MessageHandler, Sender};
 use crate::{
     app::AppMessage,
     bridge::{RedrawEvent, UiCommand},
-    event_aggregator::EVENT_AGGREGATOR,
     loggingchan::LoggingTx,
-    running_tracker::RUNNING_TRACKER,
+    session::Session,
+    settings::SettingChanged,
 };
 
-pub struct VimMessager {}
+pub struct VimMessager {
+    session: Arc<Session>,
+}
 
 impl MessageHandler<crate::app::AppModel> for VimMessager {
     type Msg = RedrawEvent;
     type Sender = LoggingTx<UiCommand>;
 
     fn init(app_model: &crate::app::AppModel, parent_sender: Sender<AppMessage>) -> Self {
-        let mut rx = EVENT_AGGREGATOR.register_event::<RedrawEvent>();
+        let session = app_model.session.clone();
+        let mut rx = session.events.register_event::<RedrawEvent>();
+        let mut settings_rx = session.events.register_event::<SettingChanged>();
         let sender = parent_sender.clone();
-        let running_tracker = RUNNING_TRACKER.clone();
+        let task_session = session.clone();
         app_model.rt.spawn(async move {
+            let running_tracker = &task_session.running_tracker;
             loop {
                 tokio::select! {
                     _ = running_tracker.wait_quit() => {
@@ -36,6 +43,12 @@ impl MessageHandler<crate::app::AppModel> for VimMessager {
                             .send(AppMessage::RedrawEvent(event))
                             .expect("Failed to send RedrawEvent to main thread");
                     },
+                    Some(SettingChanged { name, value }) = settings_rx.recv() => {
+                        log::trace!("SettingChanged {} = {}", name, value);
+                        sender
+                            .send(AppMessage::SettingChanged(name, value))
+                            .expect("Failed to send SettingChanged to main thread");
+                    },
                     else => {
                         log::info!("messager None RedrawEvent event received, quit.");
                         sender.send(AppMessage::Quit).unwrap();
@@ -45,11 +58,11 @@ impl MessageHandler<crate::app::AppModel> for VimMessager {
             }
         });
 
-        VimMessager {}
+        VimMessager { session }
     }
 
     fn send(&self, message: RedrawEvent) {
-        EVENT_AGGREGATOR.send::<RedrawEvent>(message);
+        self.session.events.send::<RedrawEvent>(message);
     }
 
     fn sender(&self) -> Self::Sender {
@@ -1,3 +1,8 @@
+use gtk::prelude::*;
+
+#[cfg(feature = "v4_12")]
+use glib::translate::{from_glib_none, ToGlibPtr};
+
 // cellwidth: f64, charwidth: f64, charheight: f64
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Metrics {
@@ -12,6 +17,10 @@ pub struct Metrics {
     /// by pango font metrics
     width: f64,
     ascent: f64,
+    /// by pango font metrics, distance from baseline down to the top of the underline.
+    underline_position: f64,
+    /// by pango font metrics
+    underline_thickness: f64,
 }
 
 impl Metrics {
@@ -26,6 +35,9 @@ impl Metrics {
             width: 1.,
             height: 2.,
             ascent: 0.,
+
+            underline_position: 1.,
+            underline_thickness: 1.,
         }
     }
 
@@ -76,4 +88,42 @@ impl Metrics {
     pub fn set_ascent(&mut self, ascent: f64) {
         self.ascent = ascent;
     }
+
+    pub fn underline_position(&self) -> f64 {
+        self.underline_position
+    }
+
+    pub fn set_underline_position(&mut self, underline_position: f64) {
+        self.underline_position = underline_position;
+    }
+
+    pub fn underline_thickness(&self) -> f64 {
+        self.underline_thickness
+    }
+
+    pub fn set_underline_thickness(&mut self, underline_thickness: f64) {
+        self.underline_thickness = underline_thickness;
+    }
+}
+
+/// Device pixels per logical pixel of the surface `widget` is on. Fractional with the `v4_12`
+/// feature, otherwise the integer scale of GTK 4.6, which the compositor downscales.
+pub fn surface_scale(widget: &impl IsA<gtk::Widget>) -> f64 {
+    #[cfg(feature = "v4_12")]
+    if let Some(surface) = widget.native().and_then(|native| surface(&native)) {
+        return unsafe { gdk_surface_get_scale(surface.to_glib_none().0) };
+    }
+    widget.scale_factor() as f64
+}
+
+/// The surface of a realized `native`.
+#[cfg(feature = "v4_12")]
+pub fn surface(native: &gtk::Native) -> Option<gtk::gdk::Surface> {
+    unsafe { from_glib_none(gtk::ffi::gtk_native_get_surface(native.to_glib_none().0)) }
+}
+
+// not bound by gtk4 0.4, new in GTK 4.12.
+#[cfg(feature = "v4_12")]
+extern "C" {
+    fn gdk_surface_get_scale(surface: *mut gtk::gdk::ffi::GdkSurface) -> std::os::raw::c_double;
 }
@@ -0,0 +1,144 @@
+/// Pointer over the text of the grids.
+pub const TEXT: &str = "text";
+/// Pointer while neovim is busy.
+pub const BUSY: &str = "wait";
+/// Pointer over cells with a url.
+pub const URL: &str = "pointer";
+
+/// Css cursor names of the shapes of vim's `'mouseshape'`, in the order of their numbers.
+const MOUSE_SHAPES: &[(&str, &str)] = &[
+    ("arrow", "default"),
+    ("blank", "none"),
+    ("beam", "text"),
+    ("updown", "ns-resize"),
+    ("udsizing", "row-resize"),
+    ("leftright", "ew-resize"),
+    ("lrsizing", "col-resize"),
+    ("busy", "wait"),
+    ("no", "not-allowed"),
+    ("crosshair", "crosshair"),
+    ("hand", "pointer"),
+    ("pen", "crosshair"),
+    ("question", "help"),
+    ("rightup-arrow", "default"),
+    ("up-arrow", "default"),
+];
+
+/// Pointer of `mouse_shape` in `mode_info_set`, sent as a number or a shape name.
+pub fn from_mouse_shape(shape: &nvim::Value) -> Option<&'static str> {
+    match shape {
+        nvim::Value::String(name) => MOUSE_SHAPES
+            .iter()
+            .find(|(shape, _)| Some(*shape) == name.as_str())
+            .map(|(_, pointer)| *pointer),
+        shape => MOUSE_SHAPES
+            .get(shape.as_u64()? as usize)
+            .map(|(_, pointer)| *pointer),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PointerSettings {
+    /// Milliseconds without moving before the pointer hides, `None` keeps it,
+    /// `g:reovim_hide_mouse_when_typing`.
+    pub hide_after: Option<u32>,
+}
+
+impl Default for PointerSettings {
+    fn default() -> Self {
+        PointerSettings {
+            hide_after: Some(1000),
+        }
+    }
+}
+
+/// Position and size of a window, in cells of the default grid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowRect {
+    pub col: u32,
+    pub row: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Pointer over a cell of the default grid that no window covers: an arrow on the last status
+/// line, resize pointers on the status lines between windows and on vertical separators.
+pub fn probe(windows: &[WindowRect], (col, row): (u32, u32)) -> Option<&'static str> {
+    let covers_cols = |win: &WindowRect| (win.col..win.col + win.width).contains(&col);
+    let covers_rows = |win: &WindowRect| (win.row..win.row + win.height).contains(&row);
+    if windows
+        .iter()
+        .any(|win| covers_cols(win) && covers_rows(win))
+    {
+        return None;
+    }
+    if windows
+        .iter()
+        .any(|win| win.col + win.width == col && covers_rows(win))
+    {
+        return Some("ew-resize");
+    }
+    if windows
+        .iter()
+        .any(|win| win.row + win.height == row && covers_cols(win))
+    {
+        let below = windows
+            .iter()
+            .any(|win| win.row == row + 1 && covers_cols(win));
+        return Some(if below { "ns-resize" } else { "default" });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mouse_shape() {
+        assert_eq!(from_mouse_shape(&0.into()), Some("default"));
+        assert_eq!(from_mouse_shape(&7.into()), Some("wait"));
+        assert_eq!(from_mouse_shape(&99.into()), None);
+        assert_eq!(from_mouse_shape(&"beam".into()), Some("text"));
+        assert_eq!(from_mouse_shape(&"unknown".into()), None);
+        assert_eq!(from_mouse_shape(&nvim::Value::Nil), None);
+    }
+
+    #[test]
+    fn test_probe() {
+        // two windows stacked on the left of a third, with the command line below.
+        //   +-----+|+-----+
+        //   |  1  |||  3  |
+        //   status||      |
+        //   |  2  |||     |
+        //   status||status|
+        let windows = [
+            WindowRect {
+                col: 0,
+                row: 0,
+                width: 10,
+                height: 5,
+            },
+            WindowRect {
+                col: 0,
+                row: 6,
+                width: 10,
+                height: 5,
+            },
+            WindowRect {
+                col: 11,
+                row: 0,
+                width: 10,
+                height: 11,
+            },
+        ];
+        assert_eq!(probe(&windows, (3, 3)), None);
+        assert_eq!(probe(&windows, (3, 5)), Some("ns-resize"));
+        assert_eq!(probe(&windows, (3, 11)), Some("default"));
+        assert_eq!(probe(&windows, (15, 11)), Some("default"));
+        assert_eq!(probe(&windows, (10, 2)), Some("ew-resize"));
+        assert_eq!(probe(&windows, (10, 8)), Some("ew-resize"));
+        // the command line.
+        assert_eq!(probe(&windows, (3, 12)), None);
+    }
+}
@@ -1,12 +1,4 @@
-use std::sync::{atomic, Arc};
-
-use once_cell::sync::Lazy;
-
-// pub static RUNNING_TRACKER: Lazy<Arc<tokio::sync::Notify>> =
-//     Lazy::new(|| Arc::new(tokio::sync::Notify::new()));
-
-pub static RUNNING_TRACKER: Lazy<Arc<RunningTracker>> =
-    Lazy::new(|| Arc::new(RunningTracker::new()));
+use std::sync::atomic;
 
 pub struct RunningTracker {
     notify: tokio::sync::Notify,
@@ -14,7 +6,7 @@ pub struct RunningTracker {
 }
 
 impl RunningTracker {
-    fn new() -> Self {
+    pub fn new() -> Self {
         RunningTracker {
             notify: tokio::sync::Notify::new(),
             exit_code: atomic::AtomicI32::new(0),
@@ -0,0 +1,128 @@
+use crate::settings::setting_group;
+
+/// Pixels per unit of smooth scroll delta, gdk reports touchpad motion divided by 10.
+pub const SMOOTH_SCROLL_PIXELS: f64 = 10.;
+/// Fraction of the velocity kinetic scrolling keeps after one second.
+const KINETIC_FRICTION: f64 = 0.05;
+/// Kinetic scrolling stops below this speed, in pixels per second.
+const KINETIC_MIN_VELOCITY: f64 = 20.;
+
+#[derive(Clone, Debug)]
+pub struct ScrollSettings {
+    /// Speed of touchpad and kinetic scrolling, `g:reovim_scroll_multiplier`.
+    pub multiplier: f64,
+    /// Keep scrolling after the fingers left the touchpad, `g:reovim_kinetic_scrolling`.
+    pub kinetic: bool,
+}
+
+impl Default for ScrollSettings {
+    fn default() -> Self {
+        ScrollSettings {
+            multiplier: 1.,
+            kinetic: true,
+        }
+    }
+}
+
+setting_group! {
+    ScrollSettings {
+        "scroll_multiplier" => multiplier if |v: &f64| *v > 0.,
+        "kinetic_scrolling" => kinetic,
+    }
+}
+
+/// Collects scroll deltas, in cells, until they add up to whole wheel events.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Accumulator {
+    x: f64,
+    y: f64,
+}
+
+impl Accumulator {
+    /// Returns the whole columns and rows scrolled, the remainder is kept for the next
+    /// delta. Turning around drops the remainder, so the first line in the new direction
+    /// does not need to make up for it.
+    pub fn add(&mut self, dx: f64, dy: f64) -> (i64, i64) {
+        fn axis(acc: &mut f64, delta: f64) -> i64 {
+            if delta != 0. && acc.signum() != delta.signum() {
+                *acc = 0.;
+            }
+            *acc += delta;
+            let cells = acc.trunc();
+            *acc -= cells;
+            cells as i64
+        }
+        (axis(&mut self.x, dx), axis(&mut self.y, dy))
+    }
+}
+
+/// Scrolling that goes on and slows down after the touchpad was released.
+#[derive(Clone, Copy, Debug)]
+pub struct Kinetic {
+    /// in pixels per second.
+    vx: f64,
+    vy: f64,
+}
+
+impl Kinetic {
+    pub fn new(vx: f64, vy: f64) -> Self {
+        Kinetic { vx, vy }
+    }
+
+    /// Advances `dt` seconds, returns the pixels travelled or `None` once it came to rest.
+    pub fn step(&mut self, dt: f64) -> Option<(f64, f64)> {
+        if self.vx.hypot(self.vy) < KINETIC_MIN_VELOCITY {
+            return None;
+        }
+        let decay = KINETIC_FRICTION.powf(dt);
+        // integral of the exponentially decaying velocity over dt.
+        let distance = (decay - 1.) / KINETIC_FRICTION.ln();
+        let travelled = (self.vx * distance, self.vy * distance);
+        self.vx *= decay;
+        self.vy *= decay;
+        Some(travelled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accumulator() {
+        let mut acc = Accumulator::default();
+        assert_eq!(acc.add(0., 0.4), (0, 0));
+        assert_eq!(acc.add(0., 0.4), (0, 0));
+        assert_eq!(acc.add(0., 0.4), (0, 1));
+        assert_eq!(acc.add(0.5, 2.5), (0, 2));
+        assert_eq!(acc.add(0.6, 0.), (1, 0));
+        // turning around starts over.
+        assert_eq!(acc.add(0., -0.6), (0, 0));
+        assert_eq!(acc.add(0., -0.6), (0, -1));
+        // a new gesture starts from a fresh accumulator, see `connect_scroll_begin`.
+        acc = Accumulator::default();
+        assert_eq!(acc.add(-3., 0.), (-3, 0));
+    }
+
+    #[test]
+    fn test_kinetic() {
+        let mut kinetic = Kinetic::new(0., 1000.);
+        let mut travelled = 0.;
+        let mut frames = 0;
+        while let Some((dx, dy)) = kinetic.step(0.016) {
+            assert_eq!(dx, 0.);
+            assert!(dy > 0.);
+            travelled += dy;
+            frames += 1;
+        }
+        assert!(frames > 10 && frames < 1000, "{} frames", frames);
+        let total = 1000. / -KINETIC_FRICTION.ln();
+        assert!(
+            travelled < total && travelled > total * 0.9,
+            "{}",
+            travelled
+        );
+
+        assert_eq!(Kinetic::new(1., 1.).step(0.016), None);
+    }
+}
@@ -0,0 +1,101 @@
+//! State of one window and the nvim behind it. Every `AppModel` owns its own, the nvim side
+//! holds a clone, so several windows can run in one process.
+
+use std::cell::Cell;
+use std::sync::Arc;
+
+use gtk::prelude::*;
+use gtk::{gdk, glib};
+use parking_lot::RwLock;
+use relm4::{AppUpdate, Components, Widgets};
+
+use crate::app::{AppComponents, AppModel, AppWidgets};
+use crate::event_aggregator::EventAggregator;
+use crate::health::Health;
+use crate::instance;
+use crate::running_tracker::RunningTracker;
+use crate::settings::Settings;
+use crate::Opts;
+
+pub struct Session {
+    /// events from the nvim side to the gui and back.
+    pub events: Arc<EventAggregator>,
+    pub settings: Settings,
+    pub running_tracker: RunningTracker,
+    pub health: RwLock<Health>,
+}
+
+impl Session {
+    pub fn new() -> Arc<Session> {
+        let events = Arc::new(EventAggregator::default());
+        Arc::new(Session {
+            settings: Settings::new(events.clone()),
+            events,
+            running_tracker: RunningTracker::new(),
+            health: RwLock::new(Health::default()),
+        })
+    }
+}
+
+/// Opens a window with its own nvim, what `relm4::RelmApp` does for a single one. The window
+/// closes when its session ends, the application exits with the last one.
+pub fn open_window(app: &gtk::Application, opts: Opts, rt: tokio::runtime::Handle) {
+    let mut model = AppModel::new(opts, rt);
+    let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+    let mut components = AppComponents::init_components(&model, sender.clone());
+    let mut widgets = AppWidgets::init_view(&model, &components, sender.clone());
+    components.connect_parent(&widgets);
+    let window = widgets.root_widget();
+    app.add_window(&window);
+    window.show();
+
+    receiver.attach(None, move |message| {
+        if !model.update(message, &components, sender.clone()) {
+            window.destroy();
+            return glib::Continue(false);
+        }
+        widgets.view(&model, sender.clone());
+        glib::Continue(true)
+    });
+}
+
+/// Opens the first window when the application is activated, later activations raise a window.
+/// All windows run their nvim on one runtime.
+pub fn run(app: &gtk::Application, opts: Opts, args: &[&str]) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_time()
+        .enable_io()
+        .build()
+        .unwrap();
+    instance::add_open_files_action(app);
+    app.connect_startup(|_| {
+        // with `g:reovim_transparency` the drawing area paints the only background.
+        let css_provider = gtk::CssProvider::new();
+        css_provider.load_from_data(b"window.transparent { background-color: transparent; }");
+        match gdk::Display::default() {
+            Some(display) => gtk::StyleContext::add_provider_for_display(
+                &display,
+                &css_provider,
+                gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+            ),
+            None => log::warn!("no display to install the stylesheet on"),
+        }
+    });
+    let opts = Cell::new(Some(opts));
+    let handle = rt.handle().clone();
+    app.connect_activate(move |app| match opts.take() {
+        Some(opts) => open_window(app, opts, handle.clone()),
+        None => {
+            if let Some(window) = app.active_window() {
+                window.present();
+            }
+        }
+    });
+    app.run_with_args(args);
+}
+
+impl std::fmt::Debug for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Session").finish_non_exhaustive()
+    }
+}
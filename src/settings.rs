@@ -1,27 +1,190 @@
 use log::trace;
 use nvim::{Neovim, Value};
-use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 use std::{
     any::{Any, TypeId},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     convert::TryInto,
+    sync::Arc,
 };
 
 use crate::bridge::TxWrapper;
+use crate::color::{Color, ColorExt};
+use crate::event_aggregator::EventAggregator;
 
-pub static SETTINGS: Lazy<Settings> = Lazy::new(|| Settings::new());
+/// Settings are exposed to nvim as `g:reovim_{name}`.
+pub const VARIABLE_PREFIX: &str = "reovim_";
+
+pub fn variable_name(name: &str) -> String {
+    format!("{}{}", VARIABLE_PREFIX, name)
+}
+
+/// Sent through the event aggregator when nvim changed `g:reovim_{name}`.
+#[derive(Clone, Debug)]
+pub struct SettingChanged {
+    pub name: String,
+    pub value: Value,
+}
 
 pub trait SettingGroup {
-    fn register(&self);
+    fn register(&self, settings: &Settings);
+}
+
+/// Conversion between a setting and its `g:reovim_{name}` variable.
+pub trait ParseFromValue: Sized {
+    fn parse_from_value(value: &Value) -> Result<Self, String>;
+
+    fn to_value(&self) -> Value;
+}
+
+/// vimscript has no booleans, `v:true` and numbers are both accepted.
+impl ParseFromValue for bool {
+    fn parse_from_value(value: &Value) -> Result<Self, String> {
+        value
+            .as_bool()
+            .or_else(|| value.as_i64().map(|v| v != 0))
+            .ok_or_else(|| format!("expected a boolean, got {}", value))
+    }
+
+    fn to_value(&self) -> Value {
+        Value::from(*self)
+    }
+}
+
+/// Whole numbers are accepted too, `let g:reovim_scale_factor = 2`.
+impl ParseFromValue for f64 {
+    fn parse_from_value(value: &Value) -> Result<Self, String> {
+        value
+            .as_f64()
+            .or_else(|| value.as_i64().map(|v| v as f64))
+            .ok_or_else(|| format!("expected a number, got {}", value))
+    }
+
+    fn to_value(&self) -> Value {
+        Value::from(*self)
+    }
+}
+
+impl ParseFromValue for u32 {
+    fn parse_from_value(value: &Value) -> Result<Self, String> {
+        value
+            .as_u64()
+            .and_then(|v| v.try_into().ok())
+            .ok_or_else(|| format!("expected a positive integer, got {}", value))
+    }
+
+    fn to_value(&self) -> Value {
+        Value::from(*self)
+    }
+}
+
+impl ParseFromValue for String {
+    fn parse_from_value(value: &Value) -> Result<Self, String> {
+        value
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("expected a string, got {}", value))
+    }
+
+    fn to_value(&self) -> Value {
+        Value::from(self.as_str())
+    }
+}
+
+/// `"#rrggbb"`, or a number like the colors of `nvim_get_hl`.
+impl ParseFromValue for Color {
+    fn parse_from_value(value: &Value) -> Result<Self, String> {
+        let invalid = || format!("expected a color like \"#1e1e2e\", got {}", value);
+        match value {
+            Value::String(color) => {
+                Color::parse(color.as_str().ok_or_else(invalid)?).map_err(|_| invalid())
+            }
+            value => value
+                .as_u64()
+                .filter(|v| *v <= 0xffffff)
+                .map(Color::from_u64)
+                .ok_or_else(invalid),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::from(self.to_hex())
+    }
+}
+
+/// An empty string, or `v:null`, leaves the setting unset.
+impl<T: ParseFromValue> ParseFromValue for Option<T> {
+    fn parse_from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Nil => Ok(None),
+            Value::String(s) if s.as_str() == Some("") => Ok(None),
+            value => T::parse_from_value(value).map(Some),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        match self {
+            Some(value) => value.to_value(),
+            None => Value::from(""),
+        }
+    }
+}
+
+/// Implements [`SettingGroup`] for a settings struct, every listed field becomes the
+/// `g:reovim_{name}` variable. Changes from nvim arrive as [`SettingChanged`], where
+/// [`Settings::parse`] checks and stores them before the gui applies them:
+///
+/// ```ignore
+/// setting_group! {
+///     ScrollSettings {
+///         "scroll_multiplier" => multiplier if |v: &f64| *v > 0.,
+///         "kinetic_scrolling" => kinetic,
+///     }
+/// }
+/// ```
+macro_rules! setting_group {
+    ($group:ty { $($name:literal => $field:ident $(if $check:expr)?),* $(,)? }) => {
+        impl $crate::settings::SettingGroup for $group {
+            fn register(&self, settings: &$crate::settings::Settings) {
+                use $crate::settings::ParseFromValue;
+                settings.set(self);
+                $(
+                    settings.set_setting_handlers(
+                        $name,
+                        |events, value| {
+                            events.send($crate::settings::SettingChanged {
+                                name: $name.to_string(),
+                                value,
+                            })
+                        },
+                        |settings| settings.get::<$group>().$field.to_value(),
+                    );
+                    settings.set_setting_parser($name, |all, value| {
+                        let mut settings = all.get::<$group>();
+                        settings.$field = ParseFromValue::parse_from_value(value)?;
+                        $(
+                            if !($check)(&settings.$field) {
+                                return Err(format!("{} is out of range", value));
+                            }
+                        )?
+                        all.set(&settings);
+                        Ok(())
+                    });
+                )*
+            }
+        }
+    };
 }
 
+pub(crate) use setting_group;
+
 // Function types to handle settings updates
-type UpdateHandlerFunc = fn(Value);
-type ReaderFunc = fn() -> Value;
+type UpdateHandlerFunc = fn(&EventAggregator, Value);
+type ReaderFunc = fn(&Settings) -> Value;
+type ParserFunc = fn(&Settings, &Value) -> Result<(), String>;
 
-// The Settings struct acts as a global container where each of Neovide's subsystems can store
-// their own settings. It will also coordinate updates between Neovide and nvim to make sure the
+// The Settings struct acts as a container, one per session, where each of Neovide's subsystems
+// can store their own settings. It will also coordinate updates between Neovide and nvim to make sure the
 // settings remain consistent on both sides.
 // Note: As right now we're only sending new setting values to Neovide during the
 // read_initial_values call, after that point we should not modify the contents of the Settings
@@ -31,14 +194,25 @@ pub struct Settings {
     settings: RwLock<HashMap<TypeId, Box<dyn Any + Send + Sync>>>,
     listeners: RwLock<HashMap<String, UpdateHandlerFunc>>,
     readers: RwLock<HashMap<String, ReaderFunc>>,
+    parsers: RwLock<HashMap<String, ParserFunc>>,
+    /// other variables that set a setting, like `g:neovide_scale_factor`.
+    aliases: RwLock<HashMap<String, Vec<String>>>,
+    /// settings whose `g:reovim_` variable the user config set, their aliases are ignored.
+    explicit: RwLock<HashSet<String>>,
+    /// where listeners send changes to, the events of the session.
+    events: Arc<EventAggregator>,
 }
 
 impl Settings {
-    fn new() -> Self {
+    pub fn new(events: Arc<EventAggregator>) -> Self {
         Self {
             settings: RwLock::new(HashMap::new()),
             listeners: RwLock::new(HashMap::new()),
             readers: RwLock::new(HashMap::new()),
+            parsers: RwLock::new(HashMap::new()),
+            aliases: RwLock::new(HashMap::new()),
+            explicit: RwLock::new(HashSet::new()),
+            events,
         }
     }
 
@@ -56,6 +230,34 @@ impl Settings {
             .insert(String::from(property_name), reader_func);
     }
 
+    pub fn set_setting_parser(&self, property_name: &str, parse_func: ParserFunc) {
+        self.parsers
+            .write()
+            .insert(String::from(property_name), parse_func);
+    }
+
+    /// `g:{variable}` sets `g:reovim_{name}` too, unless that is set by itself.
+    pub fn set_setting_alias(&self, variable: &str, property_name: &str) {
+        self.aliases
+            .write()
+            .entry(String::from(property_name))
+            .or_default()
+            .push(String::from(variable));
+    }
+
+    /// Stores a new value of a setting from a [`setting_group!`], `None` for settings the gui
+    /// parses by itself.
+    pub fn parse(&self, property_name: &str, value: &Value) -> Option<Result<(), String>> {
+        let parse = *self.parsers.read().get(property_name)?;
+        Some(parse(self, value))
+    }
+
+    /// The value of `g:reovim_{name}` as the gui sees it, to push changes made in the gui.
+    pub fn value(&self, property_name: &str) -> Option<Value> {
+        let read = *self.readers.read().get(property_name)?;
+        Some(read(self))
+    }
+
     pub fn set<T: Clone + Send + Sync + 'static>(&self, t: &T) {
         let type_id: TypeId = TypeId::of::<T>();
         let t: T = (*t).clone();
@@ -81,14 +283,24 @@ impl Settings {
         let keys: Vec<String> = self.listeners.read().keys().cloned().collect();
 
         for name in keys {
-            let variable_name = format!("neovide_{}", name);
+            let variable_name = variable_name(&name);
             match nvim.get_var(&variable_name).await {
                 Ok(value) => {
-                    self.listeners.read().get(&name).unwrap()(value);
+                    self.explicit.write().insert(name.clone());
+                    self.listeners.read().get(&name).unwrap()(&self.events, value);
                 }
                 Err(error) => {
                     trace!("Initial value load failed for {}: {}", name, error);
-                    let setting = self.readers.read().get(&name).unwrap()();
+                    let mut setting = None;
+                    let aliases = self.aliases.read().get(&name).cloned().unwrap_or_default();
+                    for alias in aliases {
+                        if let Ok(value) = nvim.get_var(&alias).await {
+                            self.listeners.read().get(&name).unwrap()(&self.events, value.clone());
+                            setting.replace(value);
+                        }
+                    }
+                    let setting =
+                        setting.unwrap_or_else(|| self.readers.read().get(&name).unwrap()(self));
                     nvim.set_var(&variable_name, setting).await.ok();
                 }
             }
@@ -102,17 +314,47 @@ impl Settings {
             let vimscript = format!(
                 concat!(
                     "exe \"",
-                    "fun! NeovideNotify{0}Changed(d, k, z)\n",
-                    "call rpcnotify(1, 'setting_changed', '{0}', g:neovide_{0})\n",
+                    "fun! ReovimNotify{0}Changed(d, k, z)\n",
+                    "call rpcnotify(1, 'setting_changed', '{0}', g:{1}{0})\n",
                     "endf\n",
-                    "call dictwatcheradd(g:, 'neovide_{0}', 'NeovideNotify{0}Changed')\"",
+                    "call dictwatcheradd(g:, '{1}{0}', 'ReovimNotify{0}Changed')\"",
                 ),
-                name
+                name, VARIABLE_PREFIX,
             );
             nvim.command(&vimscript)
                 .await
                 .expect(&format!("Could not setup setting notifier for {}", name));
         }
+
+        // an alias changed later is copied over, which notifies as above. Not for settings the
+        // user set, `g:reovim_` ones always exist by now, so nvim cannot tell them apart.
+        let explicit = self.explicit.read().clone();
+        let aliases: Vec<(String, String)> = self
+            .aliases
+            .read()
+            .iter()
+            .filter(|(name, _)| !explicit.contains(*name))
+            .flat_map(|(name, aliases)| {
+                aliases
+                    .iter()
+                    .map(move |alias| (name.clone(), alias.clone()))
+            })
+            .collect();
+        for (name, alias) in aliases {
+            let vimscript = format!(
+                concat!(
+                    "exe \"",
+                    "fun! ReovimCopy{0}(d, k, z)\n",
+                    "if has_key(g:, '{0}') | let g:{1}{2} = g:{0} | endif\n",
+                    "endf\n",
+                    "call dictwatcheradd(g:, '{0}', 'ReovimCopy{0}')\"",
+                ),
+                alias, VARIABLE_PREFIX, name,
+            );
+            nvim.command(&vimscript)
+                .await
+                .expect(&format!("Could not setup setting notifier for {}", alias));
+        }
     }
 
     pub fn handle_changed_notification(&self, arguments: Vec<Value>) {
@@ -122,7 +364,7 @@ impl Settings {
         let name: Result<String, _> = name.try_into();
         let name = name.unwrap();
 
-        self.listeners.read().get(&name).unwrap()(value);
+        self.listeners.read().get(&name).unwrap()(&self.events, value);
     }
 }
 
@@ -154,15 +396,114 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_primitives() {
+        assert_eq!(bool::parse_from_value(&Value::from(true)), Ok(true));
+        assert_eq!(bool::parse_from_value(&Value::from(0)), Ok(false));
+        assert_eq!(bool::parse_from_value(&Value::from(2)), Ok(true));
+        assert!(bool::parse_from_value(&Value::from("yes")).is_err());
+
+        assert_eq!(f64::parse_from_value(&Value::from(1.5)), Ok(1.5));
+        assert_eq!(f64::parse_from_value(&Value::from(2)), Ok(2.));
+        assert!(f64::parse_from_value(&Value::from("2")).is_err());
+
+        assert_eq!(u32::parse_from_value(&Value::from(500)), Ok(500));
+        assert!(u32::parse_from_value(&Value::from(-1)).is_err());
+        assert!(u32::parse_from_value(&Value::from(u64::MAX)).is_err());
+        assert!(u32::parse_from_value(&Value::from(1.5)).is_err());
+
+        assert_eq!(
+            String::parse_from_value(&Value::from("ripple")),
+            Ok("ripple".to_string())
+        );
+        assert!(String::parse_from_value(&Value::from(1)).is_err());
+
+        assert_eq!(true.to_value(), Value::from(true));
+        assert_eq!(1.25.to_value(), Value::from(1.25));
+        assert_eq!("x".to_string().to_value(), Value::from("x"));
+    }
+
+    #[test]
+    fn test_parse_option() {
+        assert_eq!(Option::<u32>::parse_from_value(&Value::Nil), Ok(None));
+        assert_eq!(Option::<u32>::parse_from_value(&Value::from("")), Ok(None));
+        assert_eq!(
+            Option::<u32>::parse_from_value(&Value::from(3)),
+            Ok(Some(3))
+        );
+        assert!(Option::<u32>::parse_from_value(&Value::from("3")).is_err());
+        assert_eq!(None::<u32>.to_value(), Value::from(""));
+        assert_eq!(Some(3u32).to_value(), Value::from(3));
+    }
+
+    #[test]
+    fn test_parse_color() {
+        let color = Color::parse_from_value(&Value::from("#1e1e2e")).unwrap();
+        assert_eq!(color.to_hex(), "#1e1e2e");
+        assert_eq!(color.to_value(), Value::from("#1e1e2e"));
+        let color = Color::parse_from_value(&Value::from(0xcdd6f4)).unwrap();
+        assert_eq!(color.to_hex(), "#cdd6f4");
+        assert!(Color::parse_from_value(&Value::from("not a color")).is_err());
+        assert!(Color::parse_from_value(&Value::from(0x1000000)).is_err());
+        assert!(Color::parse_from_value(&Value::from(true)).is_err());
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct TestSettings {
+        ratio: f64,
+        enabled: bool,
+    }
+
+    setting_group! {
+        TestSettings {
+            "test_ratio" => ratio if |v: &f64| (0. ..=1.).contains(v),
+            "test_enabled" => enabled,
+        }
+    }
+
+    #[test]
+    fn test_setting_group() {
+        let settings = Settings::new(Arc::default());
+        TestSettings {
+            ratio: 0.5,
+            enabled: false,
+        }
+        .register(&settings);
+        assert_eq!(settings.value("test_ratio"), Some(Value::from(0.5)));
+        assert_eq!(settings.value("test_enabled"), Some(Value::from(false)));
+
+        assert_eq!(settings.parse("test_ratio", &Value::from(1)), Some(Ok(())));
+        assert_eq!(
+            settings.parse("test_enabled", &Value::from(1)),
+            Some(Ok(()))
+        );
+        assert!(matches!(
+            settings.parse("test_ratio", &Value::from(2)),
+            Some(Err(_))
+        ));
+        assert!(matches!(
+            settings.parse("test_enabled", &Value::from("x")),
+            Some(Err(_))
+        ));
+        assert_eq!(
+            settings.get::<TestSettings>(),
+            TestSettings {
+                ratio: 1.,
+                enabled: true,
+            }
+        );
+        assert_eq!(settings.parse("test_unknown", &Value::from(1)), None);
+    }
+
     #[test]
     fn test_set_setting_handlers() {
-        let settings = Settings::new();
+        let settings = Settings::new(Arc::default());
 
         let property_name = "foo";
 
-        fn noop_update(_v: Value) {}
+        fn noop_update(_events: &EventAggregator, _v: Value) {}
 
-        fn noop_read() -> Value {
+        fn noop_read(_settings: &Settings) -> Value {
             Value::Nil
         }
 
@@ -177,7 +518,7 @@ mod tests {
 
     #[test]
     fn test_set() {
-        let settings = Settings::new();
+        let settings = Settings::new(Arc::default());
 
         let v1: u32 = 1;
         let v2: f32 = 1.0;
@@ -203,7 +544,7 @@ mod tests {
 
     #[test]
     fn test_get() {
-        let settings = Settings::new();
+        let settings = Settings::new(Arc::default());
 
         let v1: u32 = 1;
         let v2: f32 = 1.0;
@@ -227,15 +568,15 @@ mod tests {
 
     #[tokio::test]
     async fn test_read_initial_values() {
-        let settings = Settings::new();
+        let settings = Settings::new(Arc::default());
 
         let opts = Opts::default();
 
         let v1: String = "foo".to_string();
         let v2: String = "bar".to_string();
         let v3: String = "baz".to_string();
-        let v4: String = format!("neovide_{}", v1);
-        let v5: String = format!("neovide_{}", v2);
+        let v4: String = variable_name(&v1);
+        let v5: String = variable_name(&v2);
 
         //create_nvim_command tries to read from CmdLineSettings.neovim_args
         //TODO: this sets a static variable. Can this have side effects on other tests?
@@ -245,9 +586,9 @@ mod tests {
             .expect("Could not locate or start the neovim process");
         nvim.set_var(&v4, Value::from(v2.clone())).await.ok();
 
-        fn noop_update(_v: Value) {}
+        fn noop_update(_events: &EventAggregator, _v: Value) {}
 
-        fn noop_read() -> Value {
+        fn noop_read(_settings: &Settings) -> Value {
             Value::from("baz".to_string())
         }
 
@@ -0,0 +1,162 @@
+use std::path::PathBuf;
+
+/// Window state saved on exit and restored on the next launch, unless `--no-restore`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WindowState {
+    /// size of the unmaximized window, in logical pixels.
+    pub width: i32,
+    pub height: i32,
+    pub maximized: bool,
+    pub fullscreen: bool,
+    /// font zoom, `g:reovim_scale_factor`.
+    pub scale_factor: f64,
+    /// connector of the monitor the window was on, like `DP-1`.
+    pub monitor: Option<String>,
+}
+
+impl Default for WindowState {
+    fn default() -> Self {
+        WindowState {
+            width: 800,
+            height: 600,
+            maximized: false,
+            fullscreen: false,
+            scale_factor: 1.,
+            monitor: None,
+        }
+    }
+}
+
+/// `$XDG_STATE_HOME/reovim/state.toml`.
+pub fn path() -> Option<PathBuf> {
+    let state_home = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+    Some(state_home.join("reovim").join("state.toml"))
+}
+
+impl WindowState {
+    pub fn load() -> Option<WindowState> {
+        let path = path()?;
+        let content = std::fs::read_to_string(&path).ok()?;
+        match WindowState::from_toml(&content) {
+            Ok(state) => Some(state),
+            Err(err) => {
+                log::warn!("ignoring window state {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = path().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "no state directory")
+        })?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_toml())
+    }
+
+    /// Missing keys keep their defaults, so older state files still load.
+    pub fn from_toml(content: &str) -> Result<WindowState, String> {
+        let table = content
+            .parse::<toml::Value>()
+            .map_err(|err| err.to_string())?;
+        let window = match table.get("window") {
+            Some(window) => window,
+            None => return Ok(WindowState::default()),
+        };
+        let mut state = WindowState::default();
+        let int = |key: &str| match window.get(key) {
+            Some(value) => value
+                .as_integer()
+                .filter(|v| *v > 0 && *v <= i32::MAX as i64)
+                .map(|v| Some(v as i32))
+                .ok_or_else(|| format!("invalid {}: {}", key, value)),
+            None => Ok(None),
+        };
+        let bool = |key: &str| match window.get(key) {
+            Some(value) => value
+                .as_bool()
+                .map(Some)
+                .ok_or_else(|| format!("invalid {}: {}", key, value)),
+            None => Ok(None),
+        };
+        if let Some(width) = int("width")? {
+            state.width = width;
+        }
+        if let Some(height) = int("height")? {
+            state.height = height;
+        }
+        if let Some(maximized) = bool("maximized")? {
+            state.maximized = maximized;
+        }
+        if let Some(fullscreen) = bool("fullscreen")? {
+            state.fullscreen = fullscreen;
+        }
+        if let Some(value) = window.get("scale_factor") {
+            state.scale_factor = value
+                .as_float()
+                .or_else(|| value.as_integer().map(|v| v as f64))
+                .filter(|v| *v > 0.)
+                .ok_or_else(|| format!("invalid scale_factor: {}", value))?;
+        }
+        state.monitor = window
+            .get("monitor")
+            .and_then(|monitor| monitor.as_str())
+            .map(str::to_string);
+        Ok(state)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut window = toml::value::Table::new();
+        window.insert("width".into(), (self.width as i64).into());
+        window.insert("height".into(), (self.height as i64).into());
+        window.insert("maximized".into(), self.maximized.into());
+        window.insert("fullscreen".into(), self.fullscreen.into());
+        window.insert("scale_factor".into(), self.scale_factor.into());
+        if let Some(ref monitor) = self.monitor {
+            window.insert("monitor".into(), monitor.clone().into());
+        }
+        let mut table = toml::value::Table::new();
+        table.insert("window".into(), window.into());
+        toml::Value::Table(table).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let state = WindowState {
+            width: 1200,
+            height: 900,
+            maximized: true,
+            fullscreen: false,
+            scale_factor: 1.21,
+            monitor: Some("DP-1".to_string()),
+        };
+        assert_eq!(WindowState::from_toml(&state.to_toml()), Ok(state));
+    }
+
+    #[test]
+    fn test_from_toml() {
+        assert_eq!(WindowState::from_toml(""), Ok(WindowState::default()));
+        let state = WindowState::from_toml("[window]\nwidth = 1000\nscale_factor = 2\n").unwrap();
+        assert_eq!(state.width, 1000);
+        assert_eq!(state.height, 600);
+        assert_eq!(state.scale_factor, 2.);
+        assert_eq!(state.monitor, None);
+
+        assert!(WindowState::from_toml("[window]\nwidth = -1\n").is_err());
+        assert!(WindowState::from_toml("[window]\nmaximized = 1\n").is_err());
+        assert!(WindowState::from_toml("[window]\nscale_factor = 0\n").is_err());
+        assert!(WindowState::from_toml("[window\n").is_err());
+    }
+}
@@ -1,6 +1,15 @@
 use crate::color::{Color, Colors};
 
-#[derive(new, Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum UnderlineStyle {
+    Underline,
+    UnderDouble,
+    UnderCurl,
+    UnderDotted,
+    UnderDashed,
+}
+
+#[derive(new, Clone, Debug, PartialEq)]
 pub struct Style {
     pub colors: Colors,
     #[new(default)]
@@ -16,7 +25,18 @@ pub struct Style {
     #[new(default)]
     pub undercurl: bool,
     #[new(default)]
+    pub underdouble: bool,
+    #[new(default)]
+    pub underdotted: bool,
+    #[new(default)]
+    pub underdashed: bool,
+    #[new(default)]
+    pub nocombine: bool,
+    #[new(default)]
     pub blend: u8,
+    /// hyperlink target, opened on Ctrl+click.
+    #[new(default)]
+    pub url: Option<String>,
 }
 
 impl Style {
@@ -45,6 +65,23 @@ impl Style {
             .special
             .unwrap_or_else(|| self.foreground(default_colors))
     }
+
+    /// Neovim sends at most one of them, the fancier one wins anyway.
+    pub fn underline_style(&self) -> Option<UnderlineStyle> {
+        if self.undercurl {
+            Some(UnderlineStyle::UnderCurl)
+        } else if self.underdouble {
+            Some(UnderlineStyle::UnderDouble)
+        } else if self.underdashed {
+            Some(UnderlineStyle::UnderDashed)
+        } else if self.underdotted {
+            Some(UnderlineStyle::UnderDotted)
+        } else if self.underline {
+            Some(UnderlineStyle::Underline)
+        } else {
+            None
+        }
+    }
 }
 
 #[cfg(test)]
@@ -125,4 +162,21 @@ mod tests {
             DEFAULT_COLORS.special.unwrap()
         );
     }
+
+    #[test]
+    fn test_underline_style() {
+        let mut style = Style::new(COLORS);
+        assert_eq!(style.underline_style(), None);
+
+        style.underline = true;
+        assert_eq!(style.underline_style(), Some(UnderlineStyle::Underline));
+        style.underdotted = true;
+        assert_eq!(style.underline_style(), Some(UnderlineStyle::UnderDotted));
+        style.underdashed = true;
+        assert_eq!(style.underline_style(), Some(UnderlineStyle::UnderDashed));
+        style.underdouble = true;
+        assert_eq!(style.underline_style(), Some(UnderlineStyle::UnderDouble));
+        style.undercurl = true;
+        assert_eq!(style.underline_style(), Some(UnderlineStyle::UnderCurl));
+    }
 }
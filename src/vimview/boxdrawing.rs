@@ -0,0 +1,512 @@
+use std::f64::consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Weight {
+    None,
+    Light,
+    Heavy,
+    Double,
+}
+
+use Weight::{Double as D, Heavy as H, Light as L, None as N};
+
+/// Arms of U+2500 - U+257F as `[up, right, down, left]`, dashes, arcs and diagonals are drawn
+/// separately.
+#[rustfmt::skip]
+const LINES: [[Weight; 4]; 128] = [
+    [N, L, N, L], // ─
+    [N, H, N, H], // ━
+    [L, N, L, N], // │
+    [H, N, H, N], // ┃
+    [N, N, N, N], // ┄
+    [N, N, N, N], // ┅
+    [N, N, N, N], // ┆
+    [N, N, N, N], // ┇
+    [N, N, N, N], // ┈
+    [N, N, N, N], // ┉
+    [N, N, N, N], // ┊
+    [N, N, N, N], // ┋
+    [N, L, L, N], // ┌
+    [N, H, L, N], // ┍
+    [N, L, H, N], // ┎
+    [N, H, H, N], // ┏
+    [N, N, L, L], // ┐
+    [N, N, L, H], // ┑
+    [N, N, H, L], // ┒
+    [N, N, H, H], // ┓
+    [L, L, N, N], // └
+    [L, H, N, N], // ┕
+    [H, L, N, N], // ┖
+    [H, H, N, N], // ┗
+    [L, N, N, L], // ┘
+    [L, N, N, H], // ┙
+    [H, N, N, L], // ┚
+    [H, N, N, H], // ┛
+    [L, L, L, N], // ├
+    [L, H, L, N], // ┝
+    [H, L, L, N], // ┞
+    [L, L, H, N], // ┟
+    [H, L, H, N], // ┠
+    [H, H, L, N], // ┡
+    [L, H, H, N], // ┢
+    [H, H, H, N], // ┣
+    [L, N, L, L], // ┤
+    [L, N, L, H], // ┥
+    [H, N, L, L], // ┦
+    [L, N, H, L], // ┧
+    [H, N, H, L], // ┨
+    [H, N, L, H], // ┩
+    [L, N, H, H], // ┪
+    [H, N, H, H], // ┫
+    [N, L, L, L], // ┬
+    [N, L, L, H], // ┭
+    [N, H, L, L], // ┮
+    [N, H, L, H], // ┯
+    [N, L, H, L], // ┰
+    [N, L, H, H], // ┱
+    [N, H, H, L], // ┲
+    [N, H, H, H], // ┳
+    [L, L, N, L], // ┴
+    [L, L, N, H], // ┵
+    [L, H, N, L], // ┶
+    [L, H, N, H], // ┷
+    [H, L, N, L], // ┸
+    [H, L, N, H], // ┹
+    [H, H, N, L], // ┺
+    [H, H, N, H], // ┻
+    [L, L, L, L], // ┼
+    [L, L, L, H], // ┽
+    [L, H, L, L], // ┾
+    [L, H, L, H], // ┿
+    [H, L, L, L], // ╀
+    [L, L, H, L], // ╁
+    [H, L, H, L], // ╂
+    [H, L, L, H], // ╃
+    [H, H, L, L], // ╄
+    [L, L, H, H], // ╅
+    [L, H, H, L], // ╆
+    [H, H, L, H], // ╇
+    [L, H, H, H], // ╈
+    [H, L, H, H], // ╉
+    [H, H, H, L], // ╊
+    [H, H, H, H], // ╋
+    [N, N, N, N], // ╌
+    [N, N, N, N], // ╍
+    [N, N, N, N], // ╎
+    [N, N, N, N], // ╏
+    [N, D, N, D], // ═
+    [D, N, D, N], // ║
+    [N, D, L, N], // ╒
+    [N, L, D, N], // ╓
+    [N, D, D, N], // ╔
+    [N, N, L, D], // ╕
+    [N, N, D, L], // ╖
+    [N, N, D, D], // ╗
+    [L, D, N, N], // ╘
+    [D, L, N, N], // ╙
+    [D, D, N, N], // ╚
+    [L, N, N, D], // ╛
+    [D, N, N, L], // ╜
+    [D, N, N, D], // ╝
+    [L, D, L, N], // ╞
+    [D, L, D, N], // ╟
+    [D, D, D, N], // ╠
+    [L, N, L, D], // ╡
+    [D, N, D, L], // ╢
+    [D, N, D, D], // ╣
+    [N, D, L, D], // ╤
+    [N, L, D, L], // ╥
+    [N, D, D, D], // ╦
+    [L, D, N, D], // ╧
+    [D, L, N, L], // ╨
+    [D, D, N, D], // ╩
+    [L, D, L, D], // ╪
+    [D, L, D, L], // ╫
+    [D, D, D, D], // ╬
+    [N, N, N, N], // ╭
+    [N, N, N, N], // ╮
+    [N, N, N, N], // ╯
+    [N, N, N, N], // ╰
+    [N, N, N, N], // ╱
+    [N, N, N, N], // ╲
+    [N, N, N, N], // ╳
+    [N, N, N, L], // ╴
+    [L, N, N, N], // ╵
+    [N, L, N, N], // ╶
+    [N, N, L, N], // ╷
+    [N, N, N, H], // ╸
+    [H, N, N, N], // ╹
+    [N, H, N, N], // ╺
+    [N, N, H, N], // ╻
+    [N, H, N, L], // ╼
+    [L, N, H, N], // ╽
+    [N, L, N, H], // ╾
+    [H, N, L, N], // ╿
+];
+
+/// Single width blank with the same utf-8 length as every glyph drawn here, it replaces them in
+/// the pango layout so that byte indices of cells stay valid.
+pub const BLANK: &str = "\u{2007}";
+
+pub fn is_builtin(c: char) -> bool {
+    matches!(c, '\u{2500}'..='\u{259F}' | '\u{E0B0}'..='\u{E0BF}')
+}
+
+/// Returns the glyph if the whole text of a cell is drawn here.
+pub fn builtin_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    let c = chars.next()?;
+    if chars.next().is_none() && is_builtin(c) {
+        Some(c)
+    } else {
+        None
+    }
+}
+
+/// Draws `c` with the current source of `cr`, filling the cell exactly so that neighbouring
+/// cells connect without gaps.
+pub fn draw(cr: &cairo::Context, c: char, x: f64, y: f64, width: f64, height: f64) {
+    let cell = Cell {
+        x,
+        y,
+        width,
+        height,
+        light: (width / 8.).round().max(1.),
+    };
+    cr.save().ok();
+    cr.rectangle(x, y, width, height);
+    cr.clip();
+    cr.new_path();
+    match c as u32 {
+        0x2504..=0x250B | 0x254C..=0x254F => cell.dashes(cr, c as u32),
+        0x256D..=0x2570 => cell.arc(cr, c as u32),
+        0x2571..=0x2573 => cell.diagonal(cr, c as u32),
+        0x2500..=0x257F => cell.lines(cr, LINES[c as usize - 0x2500]),
+        0x2580..=0x259F => cell.block(cr, c as u32),
+        0xE0B0..=0xE0BF => cell.powerline(cr, c as u32),
+        _ => {}
+    }
+    cr.restore().ok();
+}
+
+struct Cell {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    /// thickness of light lines.
+    light: f64,
+}
+
+impl Cell {
+    fn thickness(&self, weight: Weight) -> f64 {
+        match weight {
+            N => 0.,
+            L => self.light,
+            H => self.light * 2.,
+            // two light lines and the gap between them.
+            D => self.light * 3.,
+        }
+    }
+
+    /// Top of a horizontal line with thickness `t`, on whole pixels so every cell agrees.
+    fn top(&self, t: f64) -> f64 {
+        self.y + ((self.height - t) / 2.).floor()
+    }
+
+    /// Left of a vertical line with thickness `t`.
+    fn left(&self, t: f64) -> f64 {
+        self.x + ((self.width - t) / 2.).floor()
+    }
+
+    fn hline(&self, cr: &cairo::Context, x0: f64, x1: f64, weight: Weight) {
+        let t = self.thickness(weight);
+        let top = self.top(t);
+        if weight == D {
+            cr.rectangle(x0, top, x1 - x0, self.light);
+            cr.rectangle(x0, top + self.light * 2., x1 - x0, self.light);
+        } else {
+            cr.rectangle(x0, top, x1 - x0, t);
+        }
+    }
+
+    fn vline(&self, cr: &cairo::Context, y0: f64, y1: f64, weight: Weight) {
+        let t = self.thickness(weight);
+        let left = self.left(t);
+        if weight == D {
+            cr.rectangle(left, y0, self.light, y1 - y0);
+            cr.rectangle(left + self.light * 2., y0, self.light, y1 - y0);
+        } else {
+            cr.rectangle(left, y0, t, y1 - y0);
+        }
+    }
+
+    fn lines(&self, cr: &cairo::Context, [up, right, down, left]: [Weight; 4]) {
+        // arms reach past the center far enough to cover the crossing ones.
+        let hreach = self.thickness(up).max(self.thickness(down));
+        let vreach = self.thickness(left).max(self.thickness(right));
+        let cx = self.x + (self.width / 2.).floor();
+        let cy = self.y + (self.height / 2.).floor();
+        if left != N {
+            self.hline(cr, self.x, cx + (hreach / 2.).ceil(), left);
+        }
+        if right != N {
+            self.hline(cr, cx - (hreach / 2.).floor(), self.x + self.width, right);
+        }
+        if up != N {
+            self.vline(cr, self.y, cy + (vreach / 2.).ceil(), up);
+        }
+        if down != N {
+            self.vline(cr, cy - (vreach / 2.).floor(), self.y + self.height, down);
+        }
+        cr.fill().ok();
+    }
+
+    fn dashes(&self, cr: &cairo::Context, code: u32) {
+        let (count, vertical) = match code {
+            0x2504..=0x2507 => (3, code >= 0x2506),
+            0x2508..=0x250B => (4, code >= 0x250A),
+            _ => (2, code >= 0x254E),
+        };
+        let t = if code % 2 == 0 {
+            self.light
+        } else {
+            self.light * 2.
+        };
+        let extent = if vertical { self.height } else { self.width };
+        let segment = extent / count as f64;
+        let gap = (segment / 4.).round().max(1.);
+        for i in 0..count {
+            let start = (i as f64 * segment + gap / 2.).round();
+            let len = (segment - gap).max(1.);
+            if vertical {
+                cr.rectangle(self.left(t), self.y + start, t, len);
+            } else {
+                cr.rectangle(self.x + start, self.top(t), len, t);
+            }
+        }
+        cr.fill().ok();
+    }
+
+    fn arc(&self, cr: &cairo::Context, code: u32) {
+        let (x, y, w, h) = (self.x, self.y, self.width, self.height);
+        let cx = self.left(self.light) + self.light / 2.;
+        let cy = self.top(self.light) + self.light / 2.;
+        let r = (cx - x).min(cy - y).min(x + w - cx).min(y + h - cy);
+        match code {
+            // ╭
+            0x256D => {
+                cr.move_to(cx, y + h);
+                cr.arc(cx + r, cy + r, r, PI, PI * 1.5);
+                cr.line_to(x + w, cy);
+            }
+            // ╮
+            0x256E => {
+                cr.move_to(x, cy);
+                cr.arc(cx - r, cy + r, r, PI * 1.5, PI * 2.);
+                cr.line_to(cx, y + h);
+            }
+            // ╯
+            0x256F => {
+                cr.move_to(cx, y);
+                cr.arc(cx - r, cy - r, r, 0., PI * 0.5);
+                cr.line_to(x, cy);
+            }
+            // ╰
+            _ => {
+                cr.move_to(x + w, cy);
+                cr.arc(cx + r, cy - r, r, PI * 0.5, PI);
+                cr.line_to(cx, y);
+            }
+        }
+        cr.set_line_width(self.light);
+        cr.stroke().ok();
+    }
+
+    fn diagonal(&self, cr: &cairo::Context, code: u32) {
+        let (x, y, w, h) = (self.x, self.y, self.width, self.height);
+        if code != 0x2572 {
+            cr.move_to(x + w, y);
+            cr.line_to(x, y + h);
+        }
+        if code != 0x2571 {
+            cr.move_to(x, y);
+            cr.line_to(x + w, y + h);
+        }
+        cr.set_line_width(self.light);
+        cr.stroke().ok();
+    }
+
+    fn block(&self, cr: &cairo::Context, code: u32) {
+        let (x, y, w, h) = (self.x, self.y, self.width, self.height);
+        let half_w = (w / 2.).round();
+        let half_h = (h / 2.).round();
+        match code {
+            // upper half
+            0x2580 => cr.rectangle(x, y, w, half_h),
+            // lower one eighth to full block
+            0x2581..=0x2588 => {
+                let part = (h * (code - 0x2580) as f64 / 8.).round();
+                cr.rectangle(x, y + h - part, w, part);
+            }
+            // left seven eighths to left one eighth
+            0x2589..=0x258F => {
+                let part = (w * (0x2590 - code) as f64 / 8.).round();
+                cr.rectangle(x, y, part, h);
+            }
+            // right half
+            0x2590 => cr.rectangle(x + half_w, y, w - half_w, h),
+            // light, medium and dark shade
+            0x2591..=0x2593 => {
+                cr.paint_with_alpha((code - 0x2590) as f64 / 4.).ok();
+                return;
+            }
+            // upper one eighth
+            0x2594 => cr.rectangle(x, y, w, (h / 8.).round().max(1.)),
+            // right one eighth
+            0x2595 => {
+                let part = (w / 8.).round().max(1.);
+                cr.rectangle(x + w - part, y, part, h);
+            }
+            // quadrants
+            _ => {
+                const UPPER_LEFT: u8 = 1;
+                const UPPER_RIGHT: u8 = 2;
+                const LOWER_LEFT: u8 = 4;
+                const LOWER_RIGHT: u8 = 8;
+                let quadrants = [
+                    LOWER_LEFT,
+                    LOWER_RIGHT,
+                    UPPER_LEFT,
+                    UPPER_LEFT | LOWER_LEFT | LOWER_RIGHT,
+                    UPPER_LEFT | LOWER_RIGHT,
+                    UPPER_LEFT | UPPER_RIGHT | LOWER_LEFT,
+                    UPPER_LEFT | UPPER_RIGHT | LOWER_RIGHT,
+                    UPPER_RIGHT,
+                    UPPER_RIGHT | LOWER_LEFT,
+                    UPPER_RIGHT | LOWER_LEFT | LOWER_RIGHT,
+                ][(code - 0x2596) as usize];
+                if quadrants & UPPER_LEFT != 0 {
+                    cr.rectangle(x, y, half_w, half_h);
+                }
+                if quadrants & UPPER_RIGHT != 0 {
+                    cr.rectangle(x + half_w, y, w - half_w, half_h);
+                }
+                if quadrants & LOWER_LEFT != 0 {
+                    cr.rectangle(x, y + half_h, half_w, h - half_h);
+                }
+                if quadrants & LOWER_RIGHT != 0 {
+                    cr.rectangle(x + half_w, y + half_h, w - half_w, h - half_h);
+                }
+            }
+        }
+        cr.fill().ok();
+    }
+
+    fn powerline(&self, cr: &cairo::Context, code: u32) {
+        let (x, y, w, h) = (self.x, self.y, self.width, self.height);
+        // even code points are solid, odd ones are outlines.
+        let solid = code % 2 == 0;
+        match code {
+            0xE0B0 | 0xE0B1 => {
+                cr.move_to(x, y);
+                cr.line_to(x + w, y + h / 2.);
+                cr.line_to(x, y + h);
+            }
+            0xE0B2 | 0xE0B3 => {
+                cr.move_to(x + w, y);
+                cr.line_to(x, y + h / 2.);
+                cr.line_to(x + w, y + h);
+            }
+            0xE0B4..=0xE0B7 => {
+                let right = code <= 0xE0B5;
+                cr.save().ok();
+                cr.translate(if right { x } else { x + w }, y + h / 2.);
+                cr.scale(w, h / 2.);
+                if right {
+                    cr.arc(0., 0., 1., -PI / 2., PI / 2.);
+                } else {
+                    cr.arc(0., 0., 1., PI / 2., PI * 1.5);
+                }
+                // the path is kept in device space, strokes are not distorted.
+                cr.restore().ok();
+            }
+            0xE0B8 => {
+                cr.move_to(x, y);
+                cr.line_to(x + w, y + h);
+                cr.line_to(x, y + h);
+            }
+            0xE0BA => {
+                cr.move_to(x + w, y);
+                cr.line_to(x + w, y + h);
+                cr.line_to(x, y + h);
+            }
+            0xE0BC => {
+                cr.move_to(x, y);
+                cr.line_to(x + w, y);
+                cr.line_to(x, y + h);
+            }
+            0xE0BE => {
+                cr.move_to(x, y);
+                cr.line_to(x + w, y);
+                cr.line_to(x + w, y + h);
+            }
+            // backslash
+            0xE0B9 | 0xE0BF => {
+                cr.move_to(x, y);
+                cr.line_to(x + w, y + h);
+            }
+            // slash
+            _ => {
+                cr.move_to(x, y + h);
+                cr.line_to(x + w, y);
+            }
+        }
+        if solid {
+            cr.close_path();
+            cr.fill().ok();
+        } else {
+            cr.set_line_width(self.light);
+            cr.stroke().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arms(c: char) -> [Weight; 4] {
+        LINES[c as usize - 0x2500]
+    }
+
+    #[test]
+    fn test_lines() {
+        assert_eq!(arms('─'), [N, L, N, L]);
+        assert_eq!(arms('┃'), [H, N, H, N]);
+        assert_eq!(arms('┌'), [N, L, L, N]);
+        assert_eq!(arms('┼'), [L, L, L, L]);
+        assert_eq!(arms('┞'), [H, L, L, N]);
+        assert_eq!(arms('╬'), [D, D, D, D]);
+        assert_eq!(arms('╞'), [L, D, L, N]);
+        assert_eq!(arms('╴'), [N, N, N, L]);
+        assert_eq!(arms('╿'), [H, N, L, N]);
+    }
+
+    #[test]
+    fn test_builtin_char() {
+        assert_eq!(builtin_char("│"), Some('│'));
+        assert_eq!(builtin_char("█"), Some('█'));
+        assert_eq!(builtin_char("\u{E0B0}"), Some('\u{E0B0}'));
+        assert_eq!(builtin_char("a"), None);
+        assert_eq!(builtin_char(""), None);
+        assert_eq!(builtin_char("│\u{301}"), None);
+    }
+
+    #[test]
+    fn test_blank_keeps_indices() {
+        for c in ['\u{2500}', '\u{259F}', '\u{E0B0}', '\u{E0BF}'] {
+            assert_eq!(BLANK.len(), c.len_utf8());
+        }
+    }
+}
@@ -6,9 +6,12 @@ mod imp {
     use glib::translate::{from_glib_none, ToGlibPtr};
     use gtk::traits::WidgetExt;
     use gtk::{gdk::prelude::*, graphene::Rect, subclass::prelude::*};
+    use once_cell::unsync::OnceCell;
     use parking_lot::RwLock;
 
     use crate::metrics::Metrics;
+    use crate::style::UnderlineStyle;
+    use crate::vimview::boxdrawing;
     use crate::vimview::textbuf::Lines;
     use crate::vimview::TextCell;
 
@@ -16,6 +19,8 @@ mod imp {
     use super::super::TextBuf;
 
     const PANGO_SCALE: f64 = pango::SCALE as f64;
+    /// opacity of the foreground drawn over selected cells.
+    const SELECTION_ALPHA: f64 = 0.35;
 
     #[derive(Clone, Debug)]
     struct CharAttr<'c> {
@@ -31,7 +36,10 @@ mod imp {
         width: Cell<u64>,
         height: Cell<u64>,
         is_float: Cell<bool>,
-        textbuf: Cell<TextBuf>,
+        /// set by `VimGridView::new`.
+        textbuf: OnceCell<TextBuf>,
+        /// scale the cached layouts were rendered at.
+        scale_factor: Cell<f64>,
     }
 
     impl std::fmt::Debug for VimGridView {
@@ -57,7 +65,8 @@ mod imp {
                 width: 0.into(),
                 height: 0.into(),
                 is_float: false.into(),
-                textbuf: TextBuf::default().into(),
+                textbuf: OnceCell::new(),
+                scale_factor: 1f64.into(),
             }
         }
     }
@@ -118,13 +127,11 @@ mod imp {
                 }
                 "width" => {
                     self.width.replace(value.get::<u64>().unwrap());
-                    self.textbuf()
-                        .resize(self.height.get() as _, self.width.get() as _);
+                    self.resize_textbuf();
                 }
                 "height" => {
                     self.height.replace(value.get::<u64>().unwrap());
-                    self.textbuf()
-                        .resize(self.height.get() as _, self.width.get() as _);
+                    self.resize_textbuf();
                 }
                 _ => unimplemented!(),
             }
@@ -153,6 +160,8 @@ mod imp {
 
             let hldefs = textbuf.hldefs().unwrap();
             let hldefs = hldefs.read();
+            let session = textbuf.session();
+            let settings = session.settings.get::<super::GridSettings>();
 
             let metrics = textbuf.metrics().unwrap().get();
 
@@ -163,28 +172,39 @@ mod imp {
                 .map(|style| &style.colors)
                 .and_then(|colors| colors.background)
                 .unwrap();
+            let transparency = settings.transparency as f32;
             if self.is_float.get() {
                 // float window should respect blend for background.
                 let blend = hldef.map(|style| style.blend).unwrap_or(0);
                 let alpha = (100 - blend) as f32 / 100.;
-                background.set_alpha(alpha);
+                background.set_alpha(alpha * transparency);
+            } else {
+                // the drawing area below already has the translucent background, painting it
+                // again would add up to a more opaque one.
+                background.set_alpha(if transparency < 1. { 0. } else { 1. });
             }
             snapshot.append_color(&background, &rect);
 
-            let scale_factor = widget.scale_factor();
+            // follows the surface, changes when moving between monitors.
+            let scale_factor = crate::metrics::surface_scale(widget);
+            if self.scale_factor.replace(scale_factor) != scale_factor {
+                log::debug!("grid {} rendering at scale {}", self.id.get(), scale_factor);
+                self.textbuf().reset_cache();
+            }
             let cr = snapshot.append_cairo(&rect);
-            cr.target()
-                .set_device_scale(scale_factor as f64, scale_factor as f64);
+            cr.target().set_device_scale(scale_factor, scale_factor);
 
             let mut y = metrics.ascent();
 
             let rows = textbuf.rows();
             log::debug!("text to render:");
+            let builtin_box_drawing = settings.builtin_box_drawing;
             let desc = pctx.font_description();
             let mut layout = pango::Layout::new(&pctx);
             layout.set_auto_dir(false);
             layout.set_font_description(desc.as_ref());
             let textbuf = self.textbuf();
+            let selection = textbuf.selection_spans();
             let lines = textbuf.lines();
             for lineno in 0..rows {
                 cr.move_to(0., y);
@@ -198,13 +218,28 @@ mod imp {
                     pangocairo::update_layout(&cr, &layout);
                     layoutline
                 } else {
-                    let layoutline = self.layoutline(&mut layout, &lines, lineno, &metrics);
+                    let layoutline =
+                        self.layoutline(&mut layout, &lines, lineno, &metrics, builtin_box_drawing);
                     line.set_cache(layout.copy().unwrap(), layoutline.clone());
                     pangocairo::update_layout(&cr, &layout);
                     layoutline
                 };
                 pangocairo::show_layout_line(&cr, &layoutline);
             }
+            // published once per frame, from the lines shown, cached ones included.
+            let unknown_glyphs = (0..rows)
+                .filter_map(|lineno| lines.get(lineno))
+                .map(|line| line.unknown_glyphs())
+                .sum();
+            session
+                .health
+                .write()
+                .set_unknown_glyphs(self.id.get(), unknown_glyphs);
+            if builtin_box_drawing {
+                self.draw_builtin_glyphs(&cr, &lines, rows, &hldefs, &metrics);
+            }
+            self.draw_underlines(&cr, &lines, rows, &hldefs, &metrics, &settings);
+            self.draw_selection(&cr, &selection, &hldefs, &metrics);
             let elapsed = instant.elapsed().as_secs_f32() * 1000.;
             log::info!("snapshot used: {:.3}ms", elapsed);
         }
@@ -238,11 +273,25 @@ mod imp {
         }
 
         pub(super) fn set_textbuf(&self, textbuf: TextBuf) {
-            self.textbuf.replace(textbuf);
+            self.textbuf
+                .set(textbuf)
+                .expect("the textbuf of a VimGridView is set once");
+            self.resize_textbuf();
         }
 
         pub(super) fn textbuf(&self) -> Ref<super::super::textbuf::TextBuf> {
-            unsafe { &*self.textbuf.as_ptr() }.borrow()
+            self.textbuf
+                .get()
+                .expect("VimGridView::new sets the textbuf")
+                .borrow()
+        }
+
+        fn resize_textbuf(&self) {
+            if let Some(textbuf) = self.textbuf.get() {
+                textbuf
+                    .borrow()
+                    .resize(self.height.get() as _, self.width.get() as _);
+            }
         }
 
         pub(super) fn set_width(&self, width: u64) {
@@ -271,12 +320,146 @@ mod imp {
             (w.ceil() as i32, h.ceil() as i32)
         }
 
+        /// Underlines in the `special` color, positioned by the font but aligned to cells, so
+        /// patterns continue across cells and highlights.
+        fn draw_underlines(
+            &self,
+            cr: &cairo::Context,
+            lines: &Lines,
+            rows: usize,
+            hldefs: &HighlightDefinitions,
+            metrics: &Metrics,
+            settings: &super::GridSettings,
+        ) {
+            let default_colors = hldefs.defaults().unwrap();
+            let default_style = hldefs.get(HighlightDefinitions::DEFAULT).unwrap();
+            let cellwidth = metrics.width();
+            for lineno in 0..rows {
+                let line = lines.get(lineno).unwrap();
+                let baseline = lineno as f64 * metrics.height() + metrics.ascent();
+                let bottom = (lineno + 1) as f64 * metrics.height();
+                let mut run: Option<(UnderlineStyle, gtk::gdk::RGBA, usize)> = None;
+                // one past the end flushes the last run.
+                for col in 0..=line.len() {
+                    let underline = line.get(col).and_then(|cell| {
+                        let style = cell
+                            .hldef
+                            .and_then(|hldef| hldefs.get(hldef))
+                            .unwrap_or(default_style);
+                        let color = style
+                            .colors
+                            .special
+                            .or(default_colors.special)
+                            .unwrap_or_else(|| style.foreground(default_colors));
+                        style.underline_style().map(|underline| (underline, color))
+                    });
+                    match (run, underline) {
+                        (Some((style, color, _)), Some((style_, color_)))
+                            if style == style_ && color == color_ => {}
+                        _ => {
+                            if let Some((style, color, start)) = run.take() {
+                                cr.set_source_rgba(
+                                    color.red() as _,
+                                    color.green() as _,
+                                    color.blue() as _,
+                                    color.alpha() as _,
+                                );
+                                draw_underline(
+                                    cr,
+                                    style,
+                                    (start as f64 * cellwidth, col as f64 * cellwidth),
+                                    baseline,
+                                    bottom,
+                                    metrics,
+                                    settings.undercurl_amplitude,
+                                );
+                            }
+                            run = underline.map(|(style, color)| (style, color, col));
+                        }
+                    }
+                }
+            }
+        }
+
+        /// Gui selection, a translucent foreground over the selected cells.
+        fn draw_selection(
+            &self,
+            cr: &cairo::Context,
+            spans: &[(usize, usize, usize)],
+            hldefs: &HighlightDefinitions,
+            metrics: &Metrics,
+        ) {
+            if spans.is_empty() {
+                return;
+            }
+            let default_colors = hldefs.defaults().unwrap();
+            let default_style = hldefs.get(HighlightDefinitions::DEFAULT).unwrap();
+            let color = default_style.foreground(default_colors);
+            cr.set_source_rgba(
+                color.red() as _,
+                color.green() as _,
+                color.blue() as _,
+                SELECTION_ALPHA,
+            );
+            let (width, height) = (metrics.width(), metrics.height());
+            for &(row, first, last) in spans {
+                cr.rectangle(
+                    first as f64 * width,
+                    row as f64 * height,
+                    (last + 1 - first) as f64 * width,
+                    height,
+                );
+            }
+            cr.fill().ok();
+        }
+
+        /// Box drawing, block and powerline glyphs, left blank in the layouts.
+        fn draw_builtin_glyphs(
+            &self,
+            cr: &cairo::Context,
+            lines: &Lines,
+            rows: usize,
+            hldefs: &HighlightDefinitions,
+            metrics: &Metrics,
+        ) {
+            let default_colors = hldefs.defaults().unwrap();
+            let default_style = hldefs.get(HighlightDefinitions::DEFAULT).unwrap();
+            let (width, height) = (metrics.width(), metrics.height());
+            for lineno in 0..rows {
+                let line = lines.get(lineno).unwrap();
+                for (col, cell) in line.iter().enumerate() {
+                    if let Some(c) = boxdrawing::builtin_char(&cell.text) {
+                        let style = cell
+                            .hldef
+                            .and_then(|hldef| hldefs.get(hldef))
+                            .unwrap_or(default_style);
+                        let color = style.foreground(default_colors);
+                        cr.set_source_rgba(
+                            color.red() as _,
+                            color.green() as _,
+                            color.blue() as _,
+                            color.alpha() as _,
+                        );
+                        boxdrawing::draw(
+                            cr,
+                            c,
+                            col as f64 * width,
+                            lineno as f64 * height,
+                            width,
+                            height,
+                        );
+                    }
+                }
+            }
+        }
+
         fn layoutline(
             &self,
             layout: &mut pango::Layout,
             lines: &Lines,
             lineno: usize,
             metrics: &Metrics,
+            builtin_box_drawing: bool,
         ) -> pango::LayoutLine {
             let line = lines.get(lineno).unwrap();
             let cols = line.len();
@@ -291,7 +474,11 @@ mod imp {
                 if chars.len() <= text.len() {
                     chars.resize(chars.len() * 2, None);
                 }
-                let mut chars_ = cell.text.chars();
+                let celltext = match boxdrawing::builtin_char(&cell.text) {
+                    Some(_) if builtin_box_drawing => boxdrawing::BLANK,
+                    _ => cell.text.as_str(),
+                };
+                let mut chars_ = celltext.chars();
                 let mut index = text.len();
 
                 if let Some(c) = chars_.next() {
@@ -326,7 +513,7 @@ mod imp {
                     .into();
                     index += c.to_string().bytes().len();
                 }
-                text.push_str(&cell.text);
+                text.push_str(celltext);
                 cell.attrs
                     .clone()
                     .into_iter()
@@ -345,6 +532,7 @@ mod imp {
                 metrics.charheight() * PANGO_SCALE,
                 unknown_glyphs
             );
+            line.set_unknown_glyphs(unknown_glyphs);
 
             let required_lineheight = metrics.charheight() * PANGO_SCALE;
             let real_lineheight = layout.line_readonly(0).unwrap().height() as f64;
@@ -364,7 +552,6 @@ mod imp {
                 log::debug!("Scale line height failed.");
             }
 
-
             let layoutline: pango::LayoutLine = unsafe { self.align(layout, &chars, &metrics) };
             layoutline
         }
@@ -451,6 +638,71 @@ mod imp {
             from_glib_none(layoutline)
         }
     }
+
+    /// Draws one run of underlined cells, `xs` are the left edges of its first and past the
+    /// last cell.
+    fn draw_underline(
+        cr: &cairo::Context,
+        style: UnderlineStyle,
+        (x0, x1): (f64, f64),
+        baseline: f64,
+        bottom: f64,
+        metrics: &Metrics,
+        undercurl_amplitude: f64,
+    ) {
+        let thickness = metrics.underline_thickness();
+        let cellwidth = metrics.width();
+        // keep the line inside of the cell.
+        let y =
+            (baseline + metrics.underline_position() + thickness / 2.).min(bottom - thickness / 2.);
+        cr.set_line_width(thickness);
+        cr.set_line_cap(cairo::LineCap::Butt);
+        cr.set_dash(&[], 0.);
+        match style {
+            UnderlineStyle::Underline => {
+                cr.move_to(x0, y);
+                cr.line_to(x1, y);
+            }
+            UnderlineStyle::UnderDouble => {
+                let y = y.min(bottom - thickness * 2.5);
+                cr.move_to(x0, y);
+                cr.line_to(x1, y);
+                cr.move_to(x0, y + thickness * 2.);
+                cr.line_to(x1, y + thickness * 2.);
+            }
+            UnderlineStyle::UnderDotted | UnderlineStyle::UnderDashed => {
+                let (dash, gap) = if style == UnderlineStyle::UnderDotted {
+                    (thickness, thickness)
+                } else {
+                    (cellwidth / 2., cellwidth / 2.)
+                };
+                // offset into the pattern, so it continues from the previous run.
+                cr.set_dash(&[dash, gap], x0 % (dash + gap));
+                cr.move_to(x0, y);
+                cr.line_to(x1, y);
+            }
+            UnderlineStyle::UnderCurl => {
+                let amplitude = if undercurl_amplitude > 0. {
+                    undercurl_amplitude
+                } else {
+                    (thickness * 1.5).max(1.)
+                };
+                let y = y.min(bottom - amplitude - thickness / 2.);
+                // one wave per cell, the phase only depends on x.
+                let wave =
+                    |x: f64| y + amplitude * (2. * std::f64::consts::PI * x / cellwidth).sin();
+                let step = (cellwidth / 8.).max(0.5);
+                cr.move_to(x0, wave(x0));
+                let mut x = x0;
+                while x < x1 {
+                    x = (x + step).min(x1);
+                    cr.line_to(x, wave(x));
+                }
+                cr.set_line_join(cairo::LineJoin::Round);
+            }
+        }
+        cr.stroke().ok();
+    }
 }
 
 use std::cell::{Cell, Ref};
@@ -461,6 +713,40 @@ use gtk::prelude::*;
 use parking_lot::RwLock;
 
 use super::{HighlightDefinitions, TextBuf};
+use crate::settings::setting_group;
+
+/// Rendering options shared by all grids of a window, stored in the settings of its session.
+#[derive(Clone, Debug)]
+pub struct GridSettings {
+    /// pixels, 0 derives it from the underline thickness.
+    pub undercurl_amplitude: f64,
+    /// draw box drawing, block and powerline glyphs instead of using the font.
+    pub builtin_box_drawing: bool,
+    /// opacity of the default background, `g:reovim_transparency`.
+    pub transparency: f64,
+    /// make highlighted backgrounds translucent too, `g:reovim_transparent_highlights`.
+    pub transparent_highlights: bool,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        GridSettings {
+            undercurl_amplitude: 0.,
+            builtin_box_drawing: true,
+            transparency: 1.,
+            transparent_highlights: false,
+        }
+    }
+}
+
+setting_group! {
+    GridSettings {
+        "undercurl_amplitude" => undercurl_amplitude if |v: &f64| *v >= 0.,
+        "builtin_box_drawing" => builtin_box_drawing,
+        "transparency" => transparency if |v: &f64| (0. ..=1.).contains(v),
+        "transparent_highlights" => transparent_highlights,
+    }
+}
 
 glib::wrapper! {
     pub struct VimGridView(ObjectSubclass<imp::VimGridView>)
@@ -468,16 +754,13 @@ glib::wrapper! {
         @implements gtk::Accessible, gtk::Actionable, gtk::Buildable, gtk::ConstraintTarget;
 }
 
-impl Default for VimGridView {
-    fn default() -> Self {
-        VimGridView::new(u64::MAX, 0, 0)
-    }
-}
-
 impl VimGridView {
-    pub fn new(id: u64, width: u64, height: u64) -> VimGridView {
-        glib::Object::new(&[("id", &id), ("width", &width), ("height", &height)])
-            .expect("Failed to create `VimGridView`.")
+    pub fn new(id: u64, width: u64, height: u64, textbuf: TextBuf) -> VimGridView {
+        let view: VimGridView =
+            glib::Object::new(&[("id", &id), ("width", &width), ("height", &height)])
+                .expect("Failed to create `VimGridView`.");
+        view.imp().set_textbuf(textbuf);
+        view
     }
 
     fn imp(&self) -> &imp::VimGridView {
@@ -488,10 +771,6 @@ impl VimGridView {
         self.imp().set_hldefs(hldefs);
     }
 
-    pub fn set_textbuf(&self, textbuf: TextBuf) {
-        self.imp().set_textbuf(textbuf);
-    }
-
     pub fn set_is_float(&self, is_float: bool) {
         self.imp().set_is_float(is_float);
     }
@@ -1,4 +1,4 @@
-use std::{cell::Cell, rc::Rc};
+use std::{cell::Cell, rc::Rc, sync::Arc};
 
 use glib::subclass::prelude::*;
 use gtk::prelude::*;
@@ -12,9 +12,10 @@ use crate::{
     app::AppMessage,
     bridge::{MessageKind, StyledContent},
     metrics::Metrics,
+    session::Session,
 };
 
-use super::HighlightDefinitions;
+use super::{HighlightDefinitions, TextBuf, VimGridView};
 
 mod imp {
     use std::{cell::Cell, rc::Rc};
@@ -34,7 +35,7 @@ mod imp {
     #[derive(Debug)]
     pub struct VimMessageView {
         kind: Cell<MessageKind>,
-        view: VimGridView,
+        view: OnceCell<VimGridView>,
         metrics: OnceCell<Rc<Cell<crate::metrics::Metrics>>>,
     }
 
@@ -45,9 +46,8 @@ mod imp {
         type Type = super::VimMessageView;
 
         fn new() -> Self {
-            let view = VimGridView::new(u64::MAX, 1, 1);
             Self {
-                view,
+                view: OnceCell::new(),
                 kind: Cell::new(MessageKind::Unknown),
                 metrics: OnceCell::new(),
             }
@@ -57,7 +57,6 @@ mod imp {
     // Trait shared by all GObjects
     impl ObjectImpl for VimMessageView {
         fn constructed(&self, obj: &Self::Type) {
-            obj.set_child(Some(&self.view));
             self.parent_constructed(obj);
         }
 
@@ -136,7 +135,7 @@ mod imp {
     // Trait shared by all widgets
     impl WidgetImpl for VimMessageView {
         fn snapshot(&self, widget: &Self::Type, snapshot: &gtk::Snapshot) {
-            widget.snapshot_child(&self.view, snapshot);
+            widget.snapshot_child(self.view(), snapshot);
             self.parent_snapshot(widget, snapshot);
         }
 
@@ -146,7 +145,7 @@ mod imp {
             orientation: gtk::Orientation,
             for_size: i32,
         ) -> (i32, i32, i32, i32) {
-            self.view.measure(orientation, for_size)
+            self.view().measure(orientation, for_size)
         }
     }
 
@@ -155,6 +154,17 @@ mod imp {
     }
 
     impl VimMessageView {
+        pub fn set_view(&self, obj: &super::VimMessageView, view: VimGridView) {
+            obj.set_child(Some(&view));
+            self.view
+                .set(view)
+                .expect("the view of a VimMessageView is set once");
+        }
+
+        fn view(&self) -> &VimGridView {
+            self.view.get().expect("VimMessageView::new sets the view")
+        }
+
         pub fn set_styled_context(&self, styled_content: StyledContent) {
             let (mut max_cols, mut cols, mut rows) = (1, 1, 0);
             let mut lines: Vec<Vec<GridLineCell>> = Vec::new();
@@ -190,7 +200,7 @@ mod imp {
             }
             cols = max_cols.max(cols);
             rows = rows + 1;
-            let textbuf = self.view.textbuf();
+            let textbuf = self.view().textbuf();
             textbuf.resize(rows, cols);
             for (no, cells) in lines.iter_mut().enumerate() {
                 if cells.len() < cols {
@@ -208,13 +218,13 @@ mod imp {
             }
         }
         pub fn set_pango_context(&self, pctx: Rc<pango::Context>) {
-            self.view.textbuf().set_pango_context(pctx);
+            self.view().textbuf().set_pango_context(pctx);
         }
         pub fn set_hldefs(&self, hldefs: Rc<RwLock<HighlightDefinitions>>) {
-            self.view.set_hldefs(hldefs)
+            self.view().set_hldefs(hldefs)
         }
         pub fn set_metrics(&self, metrics: Rc<Cell<Metrics>>) {
-            self.view.set_metrics(metrics.clone());
+            self.view().set_metrics(metrics.clone());
             self.metrics.set(metrics).unwrap();
         }
         pub fn set_kind(&self, kind: MessageKind) {
@@ -236,9 +246,13 @@ impl VimMessageView {
         hldefs: Rc<RwLock<HighlightDefinitions>>,
         metrics: Rc<Cell<Metrics>>,
         pctx: Rc<pango::Context>,
+        session: Arc<Session>,
     ) -> VimMessageView {
         let this: VimMessageView =
             glib::Object::new(&[]).expect("Failed to create `VimMessageView`.");
+        let textbuf = TextBuf::new(1, 1, session);
+        this.imp()
+            .set_view(&this, VimGridView::new(u64::MAX, 1, 1, textbuf));
         let name = format!("vim-message-{}", kind);
         this.set_widget_name(&name);
         this.set_css_classes(&["vim-message", &name]);
@@ -264,6 +278,7 @@ pub struct VimMessage {
     hldefs: Rc<RwLock<HighlightDefinitions>>,
     metrics: Rc<Cell<Metrics>>,
     pctx: Rc<pango::Context>,
+    session: Arc<Session>,
 }
 
 impl VimMessage {
@@ -273,6 +288,7 @@ impl VimMessage {
         hldefs: Rc<RwLock<HighlightDefinitions>>,
         metrics: Rc<Cell<Metrics>>,
         pctx: Rc<pango::Context>,
+        session: Arc<Session>,
     ) -> VimMessage {
         VimMessage {
             kind,
@@ -280,6 +296,7 @@ impl VimMessage {
             hldefs,
             metrics,
             pctx,
+            session,
         }
     }
 
@@ -313,6 +330,7 @@ impl FactoryPrototype for VimMessage {
             self.hldefs.clone(),
             self.metrics.clone(),
             self.pctx.clone(),
+            self.session.clone(),
         );
         view.set_margin_top(metrics.height() as _);
         view.set_margin_end(metrics.width() as _);
@@ -1,7 +1,9 @@
 //mod commandview;
+mod boxdrawing;
 mod gridview;
 mod highlights;
 mod messageview;
+mod selection;
 mod textbuf;
 mod widgets;
 
@@ -9,11 +11,15 @@ use std::{
     cell::RefCell,
     ops::{Deref, DerefMut},
     rc::Rc,
+    sync::Arc,
 };
 
-pub use gridview::VimGridView;
+use crate::session::Session;
+
+pub use gridview::{GridSettings, VimGridView};
 pub use highlights::HighlightDefinitions;
 pub use messageview::{MessageViewWidgets, VimMessage, VimMessageView};
+pub use selection::{Selection, SelectionMode};
 pub use textbuf::{TextCell, TextLine};
 pub use widgets::{VimGrid, VimGridWidgets};
 
@@ -21,19 +27,13 @@ pub use widgets::{VimGrid, VimGridWidgets};
 pub struct TextBuf(Rc<RefCell<textbuf::TextBuf>>);
 
 impl TextBuf {
-    pub fn new(rows: usize, cols: usize) -> Self {
-        let buf = Self::default();
+    pub fn new(rows: usize, cols: usize, session: Arc<Session>) -> Self {
+        let buf = Self(Rc::new(RefCell::new(textbuf::TextBuf::new(session))));
         buf.0.borrow_mut().resize(rows, cols);
         buf
     }
 }
 
-impl Default for TextBuf {
-    fn default() -> Self {
-        Self(Rc::new(RefCell::new(textbuf::TextBuf::new())))
-    }
-}
-
 impl Deref for TextBuf {
     type Target = Rc<RefCell<textbuf::TextBuf>>;
     fn deref(&self) -> &Self::Target {
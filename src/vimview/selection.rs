@@ -0,0 +1,295 @@
+/// What a drag selects, picked by the number of clicks that started it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionMode {
+    Cell,
+    Word,
+    Line,
+}
+
+impl SelectionMode {
+    pub fn from_clicks(clicks: i32) -> SelectionMode {
+        match clicks {
+            2 => SelectionMode::Word,
+            n if n >= 3 => SelectionMode::Line,
+            _ => SelectionMode::Cell,
+        }
+    }
+}
+
+/// Text selected with the mouse inside of one grid, while neovim does not handle the mouse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub mode: SelectionMode,
+    /// (row, col) the drag started at.
+    pub anchor: (usize, usize),
+    /// (row, col) under the pointer.
+    pub head: (usize, usize),
+}
+
+#[derive(PartialEq)]
+enum CharClass {
+    Blank,
+    Word,
+    Punct,
+}
+
+fn char_class(text: &str) -> CharClass {
+    match text.chars().next() {
+        None => CharClass::Blank,
+        Some(c) if c.is_whitespace() => CharClass::Blank,
+        Some(c) if c.is_alphanumeric() || c == '_' => CharClass::Word,
+        Some(_) => CharClass::Punct,
+    }
+}
+
+impl Selection {
+    pub fn new(mode: SelectionMode, pos: (usize, usize)) -> Selection {
+        Selection {
+            mode,
+            anchor: pos,
+            head: pos,
+        }
+    }
+
+    /// A click that did not move, nothing to select.
+    pub fn is_empty(&self) -> bool {
+        self.mode == SelectionMode::Cell && self.anchor == self.head
+    }
+
+    /// First and last (row, col) selected, both inclusive. Expanded to whole words or
+    /// lines, and to both halves of double width chars, whose right half is an empty cell.
+    pub fn range<S: AsRef<str>>(&self, lines: &[Vec<S>]) -> ((usize, usize), (usize, usize)) {
+        let (mut start, mut end) = if self.anchor <= self.head {
+            (self.anchor, self.head)
+        } else {
+            (self.head, self.anchor)
+        };
+        let text = |(row, col): (usize, usize)| {
+            lines
+                .get(row)
+                .and_then(|line| line.get(col))
+                .map(|text| text.as_ref())
+        };
+        let cols = |row: usize| lines.get(row).map(|line| line.len()).unwrap_or(0);
+        match self.mode {
+            SelectionMode::Cell => {}
+            SelectionMode::Line => {
+                start.1 = 0;
+                end.1 = cols(end.0).saturating_sub(1);
+            }
+            SelectionMode::Word => {
+                // the left half decides the class of a double width char.
+                let class = |(row, col): (usize, usize)| {
+                    let col = (0..=col)
+                        .rev()
+                        .find(|col| text((row, *col)) != Some(""))
+                        .unwrap_or(0);
+                    char_class(text((row, col)).unwrap_or(""))
+                };
+                let word = class(start);
+                while start.1 > 0 && class((start.0, start.1 - 1)) == word {
+                    start.1 -= 1;
+                }
+                let word = class(end);
+                while end.1 + 1 < cols(end.0) && class((end.0, end.1 + 1)) == word {
+                    end.1 += 1;
+                }
+            }
+        }
+        if start.1 > 0 && text(start) == Some("") {
+            start.1 -= 1;
+        }
+        if text((end.0, end.1 + 1)) == Some("") {
+            end.1 += 1;
+        }
+        (start, end)
+    }
+
+    /// Columns selected on `row`, both inclusive.
+    pub fn columns<S: AsRef<str>>(&self, lines: &[Vec<S>], row: usize) -> Option<(usize, usize)> {
+        let (start, end) = self.range(lines);
+        if row < start.0 || row > end.0 {
+            return None;
+        }
+        let last = lines.get(row)?.len().checked_sub(1)?;
+        let first = if row == start.0 { start.1 } else { 0 };
+        let last = if row == end.0 { end.1.min(last) } else { last };
+        Some((first, last))
+    }
+
+    /// The selected text, without the trailing whitespace of each line neovim pads the
+    /// grid with. Rows in `wraps` are soft-wrapped, they go on in the next row as they are.
+    /// Selected lines end with a newline, so they paste as lines.
+    pub fn text<S: AsRef<str>>(&self, lines: &[Vec<S>], wraps: &[bool]) -> String {
+        let (start, end) = self.range(lines);
+        let mut text = String::new();
+        for row in start.0..=end.0 {
+            let (first, last) = match self.columns(lines, row) {
+                Some(columns) => columns,
+                None => continue,
+            };
+            let line: String = lines[row][first..=last]
+                .iter()
+                .map(|text| text.as_ref())
+                .collect();
+            if row < end.0 && wraps.get(row).copied().unwrap_or(false) {
+                text.push_str(&line);
+                continue;
+            }
+            text.push_str(line.trim_end());
+            if row < end.0 {
+                text.push('\n');
+            }
+        }
+        match self.mode {
+            SelectionMode::Line => text + "\n",
+            _ => text,
+        }
+    }
+
+    /// The selection after the grid of `rows` x `cols` scrolled its content up by `delta`
+    /// rows, or down if negative. Rows scrolled out of the grid are cut off, `None` once
+    /// nothing is left.
+    pub fn scroll(&self, delta: isize, rows: usize, cols: usize) -> Option<Selection> {
+        let (anchor, head) = (self.anchor.0 as isize - delta, self.head.0 as isize - delta);
+        let last = rows as isize - 1;
+        if anchor.max(head) < 0 || anchor.min(head) > last {
+            return None;
+        }
+        let clamp = |row: isize, col: usize| {
+            if row < 0 {
+                (0, 0)
+            } else if row > last {
+                (last as usize, cols.saturating_sub(1))
+            } else {
+                (row as usize, col)
+            }
+        };
+        Some(Selection {
+            mode: self.mode,
+            anchor: clamp(anchor, self.anchor.1),
+            head: clamp(head, self.head.1),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(lines: &[&str]) -> Vec<Vec<String>> {
+        lines
+            .iter()
+            .map(|line| {
+                line.chars()
+                    .flat_map(|c| match c {
+                        // stands in for the empty right half of a double width char.
+                        '~' => vec![String::new()],
+                        c => vec![c.to_string()],
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn select(mode: SelectionMode, anchor: (usize, usize), head: (usize, usize)) -> Selection {
+        Selection { mode, anchor, head }
+    }
+
+    #[test]
+    fn test_cell_selection() {
+        let lines = grid(&["hello world   ", "foo bar       "]);
+        let selection = select(SelectionMode::Cell, (0, 6), (1, 2));
+        assert_eq!(selection.text(&lines, &[]), "world\nfoo");
+        // backwards drags select the same.
+        let selection = select(SelectionMode::Cell, (1, 2), (0, 6));
+        assert_eq!(selection.text(&lines, &[]), "world\nfoo");
+        assert_eq!(selection.columns(&lines, 0), Some((6, 13)));
+        assert_eq!(selection.columns(&lines, 1), Some((0, 2)));
+
+        assert!(Selection::new(SelectionMode::Cell, (0, 1)).is_empty());
+        assert!(!Selection::new(SelectionMode::Word, (0, 1)).is_empty());
+    }
+
+    #[test]
+    fn test_word_selection() {
+        let lines = grid(&["let foo_bar = a.b;  "]);
+        let word = |col| Selection::new(SelectionMode::Word, (0, col)).text(&lines, &[]);
+        assert_eq!(word(5), "foo_bar");
+        assert_eq!(word(4), "foo_bar");
+        assert_eq!(word(10), "foo_bar");
+        assert_eq!(word(12), "=");
+        assert_eq!(word(15), ".");
+        assert_eq!(word(17), ";");
+        // blanks select blanks, trimmed at the end of the line.
+        assert_eq!(word(3), "");
+        assert_eq!(word(19), "");
+
+        let selection = select(SelectionMode::Word, (0, 5), (0, 14));
+        assert_eq!(selection.text(&lines, &[]), "foo_bar = a");
+    }
+
+    #[test]
+    fn test_line_selection() {
+        let lines = grid(&["first    ", "second   ", "third    "]);
+        let selection = select(SelectionMode::Line, (1, 3), (1, 3));
+        assert_eq!(selection.text(&lines, &[]), "second\n");
+        let selection = select(SelectionMode::Line, (2, 0), (0, 5));
+        assert_eq!(selection.text(&lines, &[]), "first\nsecond\nthird\n");
+        assert_eq!(selection.columns(&lines, 2), Some((0, 8)));
+    }
+
+    #[test]
+    fn test_wrapped_lines() {
+        let lines = grid(&["a long li", "ne wraps ", "next     "]);
+        let selection = select(SelectionMode::Cell, (0, 2), (2, 3));
+        assert_eq!(
+            selection.text(&lines, &[true, false, false]),
+            "long line wraps\nnext"
+        );
+        // the blank at the wrap point is text.
+        let lines = grid(&["wrap at  ", "blank    "]);
+        let selection = select(SelectionMode::Line, (0, 0), (1, 0));
+        assert_eq!(selection.text(&lines, &[true, false]), "wrap at  blank\n");
+        assert_eq!(selection.text(&lines, &[]), "wrap at\nblank\n");
+    }
+
+    #[test]
+    fn test_scroll() {
+        let selection = select(SelectionMode::Cell, (1, 2), (3, 4));
+        assert_eq!(
+            selection.scroll(1, 5, 10),
+            Some(select(SelectionMode::Cell, (0, 2), (2, 4)))
+        );
+        assert_eq!(
+            selection.scroll(-1, 5, 10),
+            Some(select(SelectionMode::Cell, (2, 2), (4, 4)))
+        );
+        // cut off at the edges.
+        assert_eq!(
+            selection.scroll(2, 5, 10),
+            Some(select(SelectionMode::Cell, (0, 0), (1, 4)))
+        );
+        assert_eq!(
+            selection.scroll(-2, 5, 10),
+            Some(select(SelectionMode::Cell, (3, 2), (4, 9)))
+        );
+        assert_eq!(selection.scroll(4, 5, 10), None);
+        assert_eq!(selection.scroll(-4, 5, 10), None);
+    }
+
+    #[test]
+    fn test_double_width() {
+        let lines = grid(&["a中~文~b  "]);
+        // either half selects the whole char.
+        let selection = select(SelectionMode::Cell, (0, 2), (0, 3));
+        assert_eq!(selection.range(&lines), ((0, 1), (0, 4)));
+        assert_eq!(selection.text(&lines, &[]), "中文");
+        let selection = select(SelectionMode::Cell, (0, 1), (0, 1));
+        assert_eq!(selection.range(&lines), ((0, 1), (0, 2)));
+        assert_eq!(
+            Selection::new(SelectionMode::Word, (0, 4)).text(&lines, &[]),
+            "a中文b"
+        );
+    }
+}
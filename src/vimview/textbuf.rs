@@ -1,20 +1,27 @@
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
+use std::sync::Arc;
 
 use glib::subclass::prelude::*;
 use parking_lot::RwLock;
 
 use super::highlights::HighlightDefinitions;
+use super::selection::Selection;
+use super::GridSettings;
+use crate::session::Session;
 
 mod imp {
-    use std::cell::Cell;
+    use std::cell::{Cell, RefCell};
     use std::rc::Rc;
+    use std::sync::Arc;
 
     use glib::subclass::prelude::*;
     use parking_lot::{RwLock, RwLockReadGuard};
 
-    use crate::vimview::HighlightDefinitions;
+    use crate::session::Session;
+    use crate::vimview::selection::Selection;
+    use crate::vimview::{GridSettings, HighlightDefinitions};
 
     #[derive(Derivative)]
     #[derivative(Debug)]
@@ -31,6 +38,15 @@ mod imp {
 
         #[derivative(Debug = "ignore")]
         pctx: Option<Rc<pango::Context>>,
+
+        #[derivative(Debug = "ignore")]
+        font_wide: Option<Rc<RefCell<Option<pango::FontDescription>>>>,
+
+        /// always there, `TextBuf::new` takes it.
+        #[derivative(Debug = "ignore")]
+        session: Option<Arc<Session>>,
+
+        selection: Option<Selection>,
     }
 
     impl Default for _TextBuf {
@@ -49,24 +65,72 @@ mod imp {
                 pctx: None,
                 hldefs: None,
                 metrics: None,
+                font_wide: None,
+                session: None,
+                selection: None,
             }
         }
 
         fn clear(&mut self) {
             self.cells = _TextBuf::make(self.rows, self.cols);
+            self.selection = None;
+        }
+
+        fn texts(&self) -> Vec<Vec<&str>> {
+            self.cells
+                .iter()
+                .map(|line| line.iter().map(|cell| cell.text.as_str()).collect())
+                .collect()
+        }
+
+        fn wraps(&self) -> Vec<bool> {
+            self.cells.iter().map(|line| line.wrap).collect()
+        }
+
+        fn selection_spans(&self) -> Vec<(usize, usize, usize)> {
+            let selection = match self.selection {
+                Some(selection) if !selection.is_empty() => selection,
+                _ => return Vec::new(),
+            };
+            let texts = self.texts();
+            let ((first, _), (last, _)) = selection.range(&texts);
+            (first..=last)
+                .filter_map(|row| {
+                    let (start, end) = selection.columns(&texts, row)?;
+                    Some((row, start, end))
+                })
+                .collect()
+        }
+
+        fn selected_text(&self) -> Option<String> {
+            let selection = self.selection.filter(|selection| !selection.is_empty())?;
+            Some(selection.text(&self.texts(), &self.wraps()))
         }
 
         fn reset_cache(&mut self) {
             let pctx = self.pctx.as_ref().unwrap();
             let hldefs = self.hldefs.as_ref().unwrap().read();
             let metrics = self.metrics.as_ref().unwrap().get();
+            let font_wide = self.font_wide();
+            let settings = self.grid_settings();
             self.cells.iter_mut().for_each(|line| {
+                line.cache.set(None);
                 line.iter_mut().for_each(|cell| {
-                    cell.reset_attrs(pctx, &hldefs, &metrics);
+                    cell.reset_attrs(pctx, &hldefs, &metrics, font_wide.as_ref(), &settings);
                 });
             });
         }
 
+        fn grid_settings(&self) -> GridSettings {
+            self.session().settings.get::<GridSettings>()
+        }
+
+        fn font_wide(&self) -> Option<pango::FontDescription> {
+            self.font_wide
+                .as_ref()
+                .and_then(|font_wide| font_wide.borrow().clone())
+        }
+
         pub fn set_hldefs(&mut self, hldefs: Rc<RwLock<HighlightDefinitions>>) {
             self.hldefs.replace(hldefs);
         }
@@ -79,6 +143,18 @@ mod imp {
             self.pctx.replace(pctx);
         }
 
+        pub fn set_font_wide(&mut self, font_wide: Rc<RefCell<Option<pango::FontDescription>>>) {
+            self.font_wide.replace(font_wide);
+        }
+
+        fn set_session(&mut self, session: Arc<Session>) {
+            self.session.replace(session);
+        }
+
+        fn session(&self) -> Arc<Session> {
+            self.session.clone().expect("TextBuf::new sets the session")
+        }
+
         fn set_cells(&mut self, row: usize, col: usize, cells: &[crate::bridge::GridLineCell]) {
             let nrows = self.rows;
             let ncols = self.cols;
@@ -95,6 +171,8 @@ mod imp {
             let pctx = self.pctx.as_ref().unwrap();
             let hldefs = self.hldefs.as_ref().unwrap().read();
             let metrics = self.metrics.as_ref().unwrap().get();
+            let font_wide = self.font_wide();
+            let settings = self.grid_settings();
             let mut expands = Vec::with_capacity(line.len());
             let mut start_index = line.get(col).map(|cell| cell.start_index).unwrap_or(0);
             for cell in cells.iter() {
@@ -116,7 +194,7 @@ mod imp {
                         start_index,
                         end_index,
                     };
-                    cell.reset_attrs(pctx, &hldefs, &metrics);
+                    cell.reset_attrs(pctx, &hldefs, &metrics, font_wide.as_ref(), &settings);
                     log::trace!(
                         "Setting cell {}x{} start_index {} end_index {}",
                         row,
@@ -155,13 +233,22 @@ mod imp {
             line.iter_mut().fold(0, |start_index, cell| {
                 cell.start_index = start_index;
                 cell.end_index = start_index + cell.text.len();
-                cell.reset_attrs(pctx, &hldefs, &metrics);
+                cell.reset_attrs(pctx, &hldefs, &metrics, font_wide.as_ref(), &settings);
                 cell.end_index
             });
         }
 
+        fn set_wrap(&mut self, row: usize, wrap: bool) {
+            if let Some(line) = self.cells.get_mut(row) {
+                line.wrap = wrap;
+            }
+        }
+
         /// drop head of {} rows. leave tail as empty.
         fn up(&mut self, rows: usize) {
+            self.selection = self
+                .selection
+                .and_then(|selection| selection.scroll(rows as isize, self.rows, self.cols));
             let mut cells = _TextBuf::make(self.rows, self.cols);
             cells[..(self.rows - rows)].swap_with_slice(&mut self.cells[rows..]);
             self.cells = cells;
@@ -169,6 +256,9 @@ mod imp {
 
         /// drop tail of {} rows. leave head as empty.
         fn down(&mut self, rows: usize) {
+            self.selection = self
+                .selection
+                .and_then(|selection| selection.scroll(-(rows as isize), self.rows, self.cols));
             let mut cells = _TextBuf::make(self.rows, self.cols);
             cells[rows..].swap_with_slice(&mut self.cells[..(self.rows - rows)]);
             self.cells = cells;
@@ -214,6 +304,10 @@ mod imp {
             self.inner.write().set_cells(row, col, cells);
         }
 
+        pub(super) fn set_wrap(&self, row: usize, wrap: bool) {
+            self.inner.write().set_wrap(row, wrap);
+        }
+
         pub(super) fn set_hldefs(&self, hldefs: Rc<RwLock<HighlightDefinitions>>) {
             self.inner.write().set_hldefs(hldefs);
         }
@@ -226,6 +320,18 @@ mod imp {
             self.inner.write().set_pango_context(pctx);
         }
 
+        pub(super) fn set_font_wide(&self, font_wide: Rc<RefCell<Option<pango::FontDescription>>>) {
+            self.inner.write().set_font_wide(font_wide);
+        }
+
+        pub(super) fn set_session(&self, session: Arc<Session>) {
+            self.inner.write().set_session(session);
+        }
+
+        pub(super) fn session(&self) -> Arc<Session> {
+            self.inner.read().session()
+        }
+
         pub(super) fn pango_context(&self) -> Rc<pango::Context> {
             self.inner.write().pango_context()
         }
@@ -272,6 +378,22 @@ mod imp {
         pub(super) fn metrics(&self) -> Option<Rc<Cell<crate::metrics::Metrics>>> {
             self.inner.read().metrics.clone()
         }
+
+        pub(super) fn set_selection(&self, selection: Option<Selection>) {
+            self.inner.write().selection = selection;
+        }
+
+        pub(super) fn selection(&self) -> Option<Selection> {
+            self.inner.read().selection
+        }
+
+        pub(super) fn selection_spans(&self) -> Vec<(usize, usize, usize)> {
+            self.inner.read().selection_spans()
+        }
+
+        pub(super) fn selected_text(&self) -> Option<String> {
+            self.inner.read().selected_text()
+        }
     }
 
     trait TextBufExt {
@@ -292,6 +414,7 @@ mod imp {
             }
             self.cols = cols;
             self.rows = rows;
+            self.selection = None;
             let nrows = rows.min(old_rows);
             let mut cells = vec![super::TextLine::new(0); rows];
             cells[..nrows].swap_with_slice(&mut self.cells[..nrows]);
@@ -343,8 +466,11 @@ glib::wrapper! {
 }
 
 impl TextBuf {
-    pub fn new() -> Self {
-        glib::Object::new::<Self>(&[]).expect("Failed to initialize TextBuf object")
+    /// `session` gives the grid settings cells are rendered with.
+    pub fn new(session: Arc<Session>) -> Self {
+        let textbuf = glib::Object::new::<Self>(&[]).expect("Failed to initialize TextBuf object");
+        textbuf.imp().set_session(session);
+        textbuf
     }
 
     fn imp(&self) -> &imp::TextBuf {
@@ -394,6 +520,16 @@ impl TextBuf {
         self.imp().set_pango_context(pctx);
     }
 
+    /// The session of the window, for its settings and events.
+    pub fn session(&self) -> Arc<Session> {
+        self.imp().session()
+    }
+
+    /// font used by double width cells, `guifontwide`.
+    pub fn set_font_wide(&self, font_wide: Rc<RefCell<Option<pango::FontDescription>>>) {
+        self.imp().set_font_wide(font_wide);
+    }
+
     pub fn pango_context(&self) -> Rc<pango::Context> {
         self.imp().pango_context()
     }
@@ -402,6 +538,19 @@ impl TextBuf {
         self.imp().cell(row, col)
     }
 
+    /// `url` of the highlight at the cell, if any.
+    pub fn url(&self, row: usize, col: usize) -> Option<String> {
+        let hldef = self.cell(row, col)?.hldef?;
+        let hldefs = self.hldefs()?;
+        let hldefs = hldefs.read();
+        hldefs.get(hldef)?.url.clone()
+    }
+
+    /// Marks `row` as soft-wrapped, it goes on in the next row.
+    pub fn set_wrap(&self, row: usize, wrap: bool) {
+        self.imp().set_wrap(row, wrap);
+    }
+
     pub fn up(&self, rows: usize) {
         self.imp().up(rows);
     }
@@ -413,6 +562,24 @@ impl TextBuf {
     pub fn reset_cache(&self) {
         self.imp().reset_cache();
     }
+
+    pub fn set_selection(&self, selection: Option<Selection>) {
+        self.imp().set_selection(selection);
+    }
+
+    pub fn selection(&self) -> Option<Selection> {
+        self.imp().selection()
+    }
+
+    /// (row, first col, last col) of the selected cells on each line.
+    pub fn selection_spans(&self) -> Vec<(usize, usize, usize)> {
+        self.imp().selection_spans()
+    }
+
+    /// Text of the selection, `None` while nothing is selected.
+    pub fn selected_text(&self) -> Option<String> {
+        self.imp().selected_text()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -444,6 +611,8 @@ impl TextCell {
         _pctx: &pango::Context,
         hldefs: &HighlightDefinitions,
         _metrics: &crate::metrics::Metrics,
+        font_wide: Option<&pango::FontDescription>,
+        settings: &GridSettings,
     ) {
         const U16MAX: f32 = u16::MAX as f32;
 
@@ -467,6 +636,13 @@ impl TextCell {
                 hldef = style;
             }
         }
+        // must be the first one, so that bold and italic still take effect.
+        if let Some(font_wide) = font_wide.filter(|_| self.double_width) {
+            let mut attr = pango::AttrFontDesc::new(font_wide);
+            attr.set_start_index(start_index);
+            attr.set_end_index(end_index);
+            attrs.insert(attr);
+        }
         if hldef.italic {
             let mut attr = pango::AttrInt::new_style(pango::Style::Italic);
             attr.set_start_index(start_index);
@@ -485,18 +661,7 @@ impl TextCell {
             attr.set_end_index(end_index);
             attrs.insert(attr);
         }
-        if hldef.underline {
-            let mut attr = pango::AttrInt::new_underline(pango::Underline::Single);
-            attr.set_start_index(start_index);
-            attr.set_end_index(end_index);
-            attrs.insert(attr);
-        }
-        if hldef.undercurl {
-            let mut attr = pango::AttrInt::new_underline(pango::Underline::Error);
-            attr.set_start_index(start_index);
-            attr.set_end_index(end_index);
-            attrs.insert(attr);
-        }
+        // underlines are drawn by the grid view, see `VimGridView::draw_underlines`.
         // alpha color
         // blend is 0 - 100. Could be used by UIs to support
         // blending floating windows to the background or to
@@ -517,7 +682,15 @@ impl TextCell {
             attr.set_end_index(end_index);
             attrs.insert(attr);
         }
-        if let Some(bg) = background {
+        // the default background is drawn by the grid, translucent with `g:reovim_transparency`.
+        if let Some(bg) = background.filter(|bg| Some(*bg) != default_colors.background) {
+            if settings.transparent_highlights && settings.transparency < 1. {
+                let alpha = (settings.transparency * U16MAX as f64).round() as u16;
+                let mut attr = pango::AttrInt::new_background_alpha(alpha);
+                attr.set_start_index(start_index);
+                attr.set_end_index(end_index);
+                attrs.insert(attr);
+            }
             let mut attr = pango::AttrColor::new_background(
                 (bg.red() * U16MAX).round() as u16,
                 (bg.green() * U16MAX).round() as u16,
@@ -527,16 +700,6 @@ impl TextCell {
             attr.set_end_index(end_index);
             attrs.insert(attr);
         }
-        if let Some(special) = hldef.colors.special.or(default_colors.special) {
-            let mut attr = pango::AttrColor::new_underline_color(
-                (special.red() * U16MAX).round() as u16,
-                (special.green() * U16MAX).round() as u16,
-                (special.blue() * U16MAX).round() as u16,
-            );
-            attr.set_start_index(start_index);
-            attr.set_end_index(end_index);
-            attrs.insert(attr);
-        }
 
         self.attrs = attrs.attributes();
     }
@@ -546,6 +709,10 @@ impl TextCell {
 pub struct TextLine {
     boxed: Box<[TextCell]>,
     cache: Cell<Option<(pango::Layout, pango::LayoutLine)>>,
+    /// soft-wrapped by neovim, the text goes on in the next line.
+    wrap: bool,
+    /// glyphs no font has, counted when the layout is cached.
+    unknown_glyphs: Cell<i32>,
 }
 
 impl Clone for TextLine {
@@ -553,6 +720,8 @@ impl Clone for TextLine {
         TextLine {
             boxed: self.boxed.clone(),
             cache: Cell::new(unsafe { &*self.cache.as_ptr() }.clone()),
+            wrap: self.wrap,
+            unknown_glyphs: self.unknown_glyphs.clone(),
         }
     }
 }
@@ -568,6 +737,8 @@ impl TextLine {
         Self {
             boxed: line.into_boxed_slice(),
             cache: Cell::new(None),
+            wrap: false,
+            unknown_glyphs: Cell::new(0),
         }
     }
 
@@ -578,6 +749,14 @@ impl TextLine {
     pub fn set_cache(&self, layout: pango::Layout, line: pango::LayoutLine) {
         self.cache.set((layout, line).into());
     }
+
+    pub fn unknown_glyphs(&self) -> i32 {
+        self.unknown_glyphs.get()
+    }
+
+    pub fn set_unknown_glyphs(&self, count: i32) {
+        self.unknown_glyphs.set(count);
+    }
 }
 
 impl Deref for TextLine {
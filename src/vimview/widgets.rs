@@ -1,19 +1,21 @@
 use std::cell::{Cell, RefCell};
 use std::rc::Rc;
-use std::sync::atomic;
+use std::sync::{atomic, Arc};
 
 use gtk::prelude::*;
 use parking_lot::RwLock;
 use relm4::factory::positions::FixedPosition;
 use relm4::*;
 
-use crate::app::{self, Dragging};
+use crate::app::{self, Dragging, Hovering};
 use crate::bridge::{MouseAction, MouseButton, SerialCommand, UiCommand};
-use crate::event_aggregator::EVENT_AGGREGATOR;
 use crate::grapheme::{Coord, Pos, Rectangle};
+use crate::keymap::{Action, Chord};
+use crate::keys::{KeyboardSettings, ToInput};
+use crate::session::Session;
 
 use super::gridview::VimGridView;
-use super::TextBuf;
+use super::{Selection, SelectionMode, TextBuf};
 
 type HighlightDefinitions = Rc<RwLock<crate::vimview::HighlightDefinitions>>;
 
@@ -30,6 +32,9 @@ pub struct VimGrid {
     metrics: Rc<Cell<crate::metrics::Metrics>>,
     font_description: Rc<RefCell<pango::FontDescription>>,
     dragging: Rc<Cell<Option<Dragging>>>,
+    hovering: Rc<Cell<Option<Hovering>>>,
+    mouse_move_event: Rc<atomic::AtomicBool>,
+    mouse_on: Rc<atomic::AtomicBool>,
 
     textbuf: TextBuf,
 
@@ -45,10 +50,14 @@ impl VimGrid {
         rect: Rectangle,
         hldefs: HighlightDefinitions,
         dragging: Rc<Cell<Option<Dragging>>>,
+        hovering: Rc<Cell<Option<Hovering>>>,
+        mouse_move_event: Rc<atomic::AtomicBool>,
+        mouse_on: Rc<atomic::AtomicBool>,
         metrics: Rc<Cell<crate::metrics::Metrics>>,
         font_description: Rc<RefCell<pango::FontDescription>>,
+        session: Arc<Session>,
     ) -> VimGrid {
-        let textbuf = TextBuf::new(rect.height, rect.width);
+        let textbuf = TextBuf::new(rect.height, rect.width, session);
         textbuf.borrow().set_hldefs(hldefs.clone());
         textbuf.borrow().set_metrics(metrics.clone());
         let m = metrics.get();
@@ -61,6 +70,9 @@ impl VimGrid {
             height: rect.height as _,
             move_to: None.into(),
             dragging,
+            hovering,
+            mouse_move_event,
+            mouse_on,
             is_float: false,
             focusable: true,
             metrics,
@@ -87,6 +99,14 @@ impl VimGrid {
         &self.coord
     }
 
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn is_float(&self) -> bool {
+        self.is_float
+    }
+
     pub fn hide(&mut self) {
         self.visible = false;
     }
@@ -161,6 +181,23 @@ impl VimGrid {
     pub fn set_pango_context(&self, pctx: Rc<pango::Context>) {
         self.textbuf().borrow().set_pango_context(pctx);
     }
+
+    pub fn set_font_wide(&self, font_wide: Rc<RefCell<Option<pango::FontDescription>>>) {
+        self.textbuf().borrow().set_font_wide(font_wide);
+    }
+
+    /// Text of the gui selection in this grid, if any.
+    pub fn selected_text(&self) -> Option<String> {
+        self.textbuf().borrow().selected_text()
+    }
+
+    /// Returns whether there was a selection to clear.
+    pub fn clear_selection(&self) -> bool {
+        let textbuf = self.textbuf().borrow();
+        let cleared = textbuf.selection().is_some();
+        textbuf.set_selection(None);
+        cleared
+    }
 }
 
 #[derive(Debug)]
@@ -177,10 +214,11 @@ impl factory::FactoryPrototype for VimGrid {
 
     fn init_view(&self, grid: &u64, sender: Sender<app::AppMessage>) -> VimGridWidgets {
         let grid = *grid;
+        // mouse input goes to nvim directly, not through the app.
+        let events = self.textbuf.borrow().session().events.clone();
         view! {
-            view = VimGridView::new(grid, self.width as _, self.height as _) {
+            view = VimGridView::new(grid, self.width as _, self.height as _, self.textbuf.clone()) {
                 set_widget_name: &format!("vim-grid-{}-{}", self.win, grid),
-                set_textbuf: self.textbuf.clone(),
 
                 set_visible: self.visible,
                 set_can_focus: true,
@@ -203,7 +241,7 @@ impl factory::FactoryPrototype for VimGrid {
             .name("click-listener")
             .build();
         click_listener.connect_pressed(
-            glib::clone!(@strong sender, @weak self.dragging as dragging, @weak self.metrics as metrics => move |c, n_press, x, y| {
+            glib::clone!(@strong sender, @strong events, @weak self.dragging as dragging, @weak self.mouse_on as mouse_on, @weak self.metrics as metrics, @strong self.textbuf as textbuf => move |c, n_press, x, y| {
                 sender.send(app::AppMessage::ShowPointer).unwrap();
                 let metrics = metrics.get();
                 let width = metrics.width();
@@ -212,6 +250,29 @@ impl factory::FactoryPrototype for VimGrid {
                 let rows = y as f64 / height;
                 log::trace!("grid {} mouse pressed {} times at {}x{} -> {}x{}", grid, n_press, x, y, cols, rows);
                 let position = (cols.floor() as u32, rows.floor() as u32);
+                if let Some(url) = ctrl_clicked_url(c, &textbuf, position) {
+                    log::info!("opening {}", url);
+                    let window = c.widget().root().and_then(|root| root.downcast::<gtk::Window>().ok());
+                    gtk::show_uri(window.as_ref(), &url, c.current_event_time());
+                    return;
+                }
+                if let Some(action) = middle_click_action(c, &textbuf) {
+                    // paste where the click was, like terminals do.
+                    if action == Action::PastePrimary && mouse_on.load(atomic::Ordering::Relaxed) {
+                        for action in [MouseAction::Press, MouseAction::Release] {
+                            events.send(UiCommand::Serial(SerialCommand::MouseButton {
+                                action,
+                                button: MouseButton::Left,
+                                modifier: gtk::gdk::ModifierType::empty(),
+                                clicks: 1,
+                                grid_id: grid,
+                                position,
+                            }));
+                        }
+                    }
+                    sender.send(app::AppMessage::Action(action)).unwrap();
+                    return;
+                }
                 let modifier = c.current_event_state().to_string();
                 let btn = match c.current_button() {
                     1 => MouseButton::Left,
@@ -219,12 +280,37 @@ impl factory::FactoryPrototype for VimGrid {
                     3 => MouseButton::Right,
                     _ => { return; }
                 };
-                dragging.set(Dragging{ btn, pos: position}.into());
-                EVENT_AGGREGATOR.send(
+                // drags are sent to this grid wherever the pointer goes.
+                let view = c.widget();
+                let origin = view
+                    .parent()
+                    .and_then(|parent| view.translate_coordinates(&parent, 0., 0.))
+                    .unwrap_or_default();
+                let select = matches!(btn, MouseButton::Left)
+                    && (!mouse_on.load(atomic::Ordering::Relaxed)
+                        || c.current_event_state().contains(gtk::gdk::ModifierType::SHIFT_MASK));
+                dragging.set(Dragging { btn, grid, origin, pos: position, select }.into());
+                let except = if select { Some(grid) } else { None };
+                sender.send(app::AppMessage::ClearSelection(except)).unwrap();
+                if select {
+                    let selection = Selection::new(
+                        SelectionMode::from_clicks(n_press),
+                        (position.1 as usize, position.0 as usize),
+                    );
+                    textbuf.borrow().set_selection(Some(selection));
+                    view.queue_draw();
+                    // words and lines are selected by the click alone.
+                    if let Some(text) = textbuf.borrow().selected_text() {
+                        view.primary_clipboard().set_text(&text);
+                    }
+                    return;
+                }
+                events.send(
                     UiCommand::Serial(SerialCommand::MouseButton {
                         action: MouseAction::Press,
                         button: btn,
                         modifier: c.current_event_state(),
+                        clicks: n_press,
                         grid_id: grid,
                         position
                     })
@@ -233,7 +319,7 @@ impl factory::FactoryPrototype for VimGrid {
             }),
         );
         click_listener.connect_released(
-            glib::clone!(@strong sender, @weak self.dragging as dragging, @weak self.metrics as metrics => move |c, n_press, x, y| {
+            glib::clone!(@strong sender, @strong events, @weak self.dragging as dragging, @weak self.metrics as metrics, @strong self.textbuf as textbuf => move |c, n_press, x, y| {
                 sender.send(app::AppMessage::ShowPointer).unwrap();
                 let metrics = metrics.get();
                 let width = metrics.width();
@@ -241,21 +327,40 @@ impl factory::FactoryPrototype for VimGrid {
                 let cols = x as f64 / width;
                 let rows = y as f64 / height;
                 log::trace!("grid {} mouse released {} times at {}x{} -> {}x{}", grid, n_press, x, y, cols, rows);
+                // the press already opened the url.
+                if ctrl_clicked_url(c, &textbuf, (cols.floor() as u32, rows.floor() as u32)).is_some() {
+                    return;
+                }
+                // the press ran the action.
+                if middle_click_action(c, &textbuf).is_some() {
+                    return;
+                }
+                if let Some(Dragging { select: true, .. }) = dragging.take() {
+                    let textbuf = textbuf.borrow();
+                    match textbuf.selected_text() {
+                        Some(text) => c.widget().primary_clipboard().set_text(&text),
+                        None => {
+                            textbuf.set_selection(None);
+                            c.widget().queue_draw();
+                        }
+                    }
+                    return;
+                }
                 let modifier = c.current_event_state().to_string();
-                dragging.set(None);
                 let btn = match c.current_button() {
                     1 => MouseButton::Left,
                     2 => MouseButton::Middle,
                     3 => MouseButton::Right,
                     _ => { return; }
                 };
-                EVENT_AGGREGATOR.send(
+                events.send(
                     UiCommand::Serial(SerialCommand::MouseButton {
                         action: MouseAction::Release,
                         button: btn,
                         modifier: c.current_event_state(),
+                        clicks: 1,
                         grid_id: grid,
-                        position: (cols.floor() as u32, rows.floor() as u32)
+                        position: (cols.floor().max(0.) as u32, rows.floor().max(0.) as u32)
                     })
                 );
                 log::trace!("grid {} release button {} current_button {} modifier {}", grid, c.button(), c.current_button(), modifier);
@@ -264,32 +369,87 @@ impl factory::FactoryPrototype for VimGrid {
         view.add_controller(&click_listener);
 
         let motion_listener = gtk::EventControllerMotion::new();
-        let grid_id = grid;
-        motion_listener.connect_enter(move |_, _, _| {
-            app::GridActived.store(grid_id, atomic::Ordering::Relaxed);
-        });
-        motion_listener.connect_motion(glib::clone!(@strong sender, @weak self.dragging as dragging, @weak self.metrics as metrics => move |c, x, y| {
+        motion_listener.connect_enter(glib::clone!(@strong events, @weak self.hovering as hovering, @weak self.mouse_move_event as mouse_move_event, @weak self.metrics as metrics => move |c, x, y| {
+            let metrics = metrics.get();
+            let position = ((x / metrics.width()).floor() as u32, (y / metrics.height()).floor() as u32);
+            hovering.set(Hovering { grid, pos: position }.into());
+            if mouse_move_event.load(atomic::Ordering::Relaxed) {
+                events.send(
+                    UiCommand::Serial(SerialCommand::MouseMove {
+                        modifier: c.current_event_state(),
+                        grid_id: grid,
+                        position,
+                    })
+                );
+            }
+        }));
+        motion_listener.connect_leave(glib::clone!(@weak self.hovering as hovering => move |_| {
+            if matches!(hovering.get(), Some(Hovering { grid: hovered, .. }) if hovered == grid) {
+                hovering.set(None);
+            }
+        }));
+        motion_listener.connect_motion(glib::clone!(@strong sender, @strong events, @weak self.dragging as dragging, @weak self.hovering as hovering, @weak self.mouse_move_event as mouse_move_event, @weak self.metrics as metrics, @strong self.textbuf as textbuf => move |c, x, y| {
             sender.send(app::AppMessage::ShowPointer).unwrap();
             log::trace!("cursor motion {} {}", x, y);
-            if let Some(Dragging { btn, pos }) = dragging.get() {
-                let metrics = metrics.get();
-                let width = metrics.width();
-                let height = metrics.height();
-                let cols = x as f64 / width;
-                let rows = y as f64 / height;
-                let position = (cols.floor() as u32, rows.floor() as u32);
-                log::trace!("Dragging {} from {:?} to {:?}", btn, pos, position);
+            let metrics = metrics.get();
+            let widget = c.widget();
+            if let Some(Dragging { btn, grid: target, origin, pos, select }) = dragging.get() {
+                // relative to the grid the drag started in, which may be another one.
+                let (x, y) = widget
+                    .parent()
+                    .and_then(|parent| widget.translate_coordinates(&parent, x, y))
+                    .map(|(x, y)| (x - origin.0, y - origin.1))
+                    .unwrap_or((x, y));
+                let position = (
+                    (x / metrics.width()).floor().max(0.) as u32,
+                    (y / metrics.height()).floor().max(0.) as u32,
+                );
+                log::trace!("Dragging {} in grid {} from {:?} to {:?}", btn, target, pos, position);
+                if select {
+                    // selections stay inside of the grid they started in.
+                    if pos != position && target == grid {
+                        let textbuf = textbuf.borrow();
+                        if let Some(mut selection) = textbuf.selection() {
+                            selection.head = (
+                                (position.1 as usize).min(textbuf.rows().saturating_sub(1)),
+                                (position.0 as usize).min(textbuf.cols().saturating_sub(1)),
+                            );
+                            textbuf.set_selection(Some(selection));
+                            widget.queue_draw();
+                        }
+                        dragging.set(Dragging { btn, grid, origin, pos: position, select }.into());
+                    }
+                    return;
+                }
                 if pos != position {
-                    EVENT_AGGREGATOR.send(
+                    events.send(
                         UiCommand::Serial(SerialCommand::Drag {
                             button: btn,
                             modifier: c.current_event_state(),
-                            grid_id: grid,
+                            grid_id: target,
                             position,
                         })
                     );
-                    dragging.set(Dragging { btn, pos: position }.into());
+                    dragging.set(Dragging { btn, grid: target, origin, pos: position, select }.into());
                 }
+                return;
+            }
+            if !widget.contains(x, y) {
+                return;
+            }
+            let col = (x / metrics.width()).floor() as usize;
+            let row = (y / metrics.height()).floor() as usize;
+            let position = (col as u32, row as u32);
+            let moved = hovering.get() != Some(Hovering { grid, pos: position });
+            hovering.set(Hovering { grid, pos: position }.into());
+            if moved && mouse_move_event.load(atomic::Ordering::Relaxed) {
+                events.send(
+                    UiCommand::Serial(SerialCommand::MouseMove {
+                        modifier: c.current_event_state(),
+                        grid_id: grid,
+                        position,
+                    })
+                );
             }
             // for mouse auto hide
             // if motion show one second.
@@ -344,3 +504,36 @@ impl factory::FactoryPrototype for VimGrid {
         &widgets.view
     }
 }
+
+/// Gui action a middle click is bound to in `g:reovim_keymap`, by default a plain `<MiddleMouse>`
+/// pastes the primary selection. Unbound, or bound to itself, the click goes to nvim.
+fn middle_click_action(c: &gtk::GestureClick, textbuf: &TextBuf) -> Option<Action> {
+    if c.current_button() != 2 {
+        return None;
+    }
+    let settings = textbuf.session().settings.get::<KeyboardSettings>();
+    let modifier = settings.modifiers(c.current_event_state()).to_input()?;
+    let input = format!("<{}MiddleMouse>", modifier);
+    match settings.keymap.get(&input)? {
+        Action::Keys(keys) if Chord::parse(keys) == Chord::parse(&input) => None,
+        action => Some(action.clone()),
+    }
+}
+
+/// Url under a Ctrl+click with the primary button.
+fn ctrl_clicked_url(
+    c: &gtk::GestureClick,
+    textbuf: &TextBuf,
+    position: (u32, u32),
+) -> Option<String> {
+    if c.current_button() != 1
+        || !c
+            .current_event_state()
+            .contains(gtk::gdk::ModifierType::CONTROL_MASK)
+    {
+        return None;
+    }
+    textbuf
+        .borrow()
+        .url(position.1 as usize, position.0 as usize)
+}